@@ -0,0 +1,133 @@
+//! Lining up two commits' memory profiles in time.
+//!
+//! If an early pass gets slower, everything after it happens later,
+//! so subtracting the raw curves mostly measures that shift. Instead,
+//! we map the time axis of one commit onto the other via pairs of
+//! corresponding times ("anchors"): the boundaries of the passes they
+//! both ran if we know them, or a dynamic time warping of the curves
+//! themselves if we don't.
+
+use std::f64;
+use resample::{mod, linear, Linear};
+use pass_memory::{mod, PassMemory};
+
+/// The number of points each curve is resampled to for DTW.
+static DTW_POINTS: uint = 200;
+
+/// A commit's aligned difference from the commit before it, as
+/// written to out/aligned/<hash>.json for the detail view.
+#[deriving(Encodable)]
+pub struct Aligned {
+    pub parent: String,
+    /// (parent's time, this commit's memory - the parent's memory)
+    pub aligned_memory: Vec<(f64, f64)>,
+}
+
+/// b's memory minus a's, at each of a's sample times, after warping
+/// b's time axis onto a's.
+pub fn aligned_difference(a: &[(f64, f64)], a_passes: &[(String, f64)],
+                          b: &[(f64, f64)], b_passes: &[(String, f64)]) -> Vec<(f64, f64)> {
+    if a.is_empty() || b.is_empty() { return vec![] }
+
+    let mut anchors = pass_anchors(pass_memory::attribute(a, a_passes).as_slice(),
+                                   pass_memory::attribute(b, b_passes).as_slice());
+    if anchors.is_empty() {
+        anchors = dtw_anchors(a, b);
+    }
+
+    // the traces start and end together, whatever happens inside.
+    let ((a_start, _), (b_start, _)) = (a[0], b[0]);
+    let ((a_end, _), (b_end, _)) = (a[a.len() - 1], b[b.len() - 1]);
+    anchors.insert(0, (a_start, b_start));
+    anchors.push((a_end, b_end));
+    let anchors = increasing(anchors.as_slice());
+
+    a.iter().map(|&(t, m)| {
+        (t, linear(b, linear(anchors.as_slice(), t)) - m)
+    }).collect()
+}
+
+/// The starts and ends of the passes that both commits ran, matched
+/// up by name, in order.
+fn pass_anchors(a: &[PassMemory], b: &[PassMemory]) -> Vec<(f64, f64)> {
+    let mut anchors = vec![];
+    let mut j = 0;
+    for pa in a.iter() {
+        match b.slice_from(j).iter().position(|pb| pb.name == pa.name) {
+            Some(k) => {
+                let pb = &b[j + k];
+                anchors.push((pa.start, pb.start));
+                anchors.push((pa.end, pb.end));
+                j += k + 1;
+            }
+            None => {}
+        }
+    }
+    anchors
+}
+
+/// Corresponding times in `a` and `b` from the dynamic time warping
+/// of their memory curves.
+fn dtw_anchors(a: &[(f64, f64)], b: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let (ta, ya) = uniform(a, DTW_POINTS);
+    let (tb, yb) = uniform(b, DTW_POINTS);
+    let n = DTW_POINTS;
+
+    // cost[i * n + j] is the cheapest way to match a[..i+1] with b[..j+1].
+    let mut cost = Vec::from_elem(n * n, f64::INFINITY);
+    for i in range(0, n) {
+        for j in range(0, n) {
+            let prev = if i == 0 && j == 0 {
+                0.0
+            } else {
+                let up = if i > 0 { cost[(i - 1) * n + j] } else { f64::INFINITY };
+                let left = if j > 0 { cost[i * n + j - 1] } else { f64::INFINITY };
+                let diag = if i > 0 && j > 0 { cost[(i - 1) * n + j - 1] } else { f64::INFINITY };
+                up.min(left).min(diag)
+            };
+            *cost.get_mut(i * n + j) = prev + (ya[i] - yb[j]).abs();
+        }
+    }
+
+    // walk back along the cheapest path.
+    let (mut i, mut j) = (n - 1, n - 1);
+    let mut path = vec![(ta[i], tb[j])];
+    while i > 0 || j > 0 {
+        let up = if i > 0 { cost[(i - 1) * n + j] } else { f64::INFINITY };
+        let left = if j > 0 { cost[i * n + j - 1] } else { f64::INFINITY };
+        let diag = if i > 0 && j > 0 { cost[(i - 1) * n + j - 1] } else { f64::INFINITY };
+        if diag <= up && diag <= left {
+            i -= 1;
+            j -= 1;
+        } else if up <= left {
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+        path.push((ta[i], tb[j]));
+    }
+    path.reverse();
+    path
+}
+
+/// `n` evenly spaced times across `xs`, and its value at each.
+fn uniform(xs: &[(f64, f64)], n: uint) -> (Vec<f64>, Vec<f64>) {
+    let ((start, _), (end, _)) = (xs[0], xs[xs.len() - 1]);
+    let ts = resample::evenly(start, end, n);
+    let ys = resample::sample(xs, ts.as_slice(), Linear);
+    (ts, ys)
+}
+
+/// The anchors that are strictly after all the previous ones in both
+/// commits, so the warping never goes backwards.
+fn increasing(anchors: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut ret: Vec<(f64, f64)> = vec![];
+    for &(x, y) in anchors.iter() {
+        let ok = match ret.last() {
+            Some(&(px, py)) => x > px && y > py,
+            None => true
+        };
+        if ok { ret.push((x, y)) }
+    }
+    ret
+}
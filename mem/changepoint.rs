@@ -0,0 +1,132 @@
+//! Finding the places where a noisy series changes level.
+//!
+//! This uses PELT (Killick, Fearnhead & Eckley, 2012) with a
+//! Gaussian change-in-mean cost, and then throws away any changes
+//! that are too small to stand out from the noise.
+
+use std::f64;
+use std::f64::consts::SQRT2;
+use noise::{median, sigma};
+
+/// A shift in level of a series.
+#[deriving(Clone, PartialEq, Show)]
+pub struct ChangePoint {
+    /// The index of the first point at the new level.
+    pub index: uint,
+    /// The (median) level of the segment before and after.
+    pub before: f64,
+    pub after: f64,
+    /// How sure we are that this isn't just noise, in [0, 1].
+    pub confidence: f64,
+}
+
+/// The shortest run of points we'll accept as a level of its own,
+/// so that a single outlier isn't reported as two changes.
+static MIN_SEGMENT: uint = 3;
+/// How many noise standard deviations a shift has to be to count.
+static MIN_SIGMAS: f64 = 3.0;
+
+/// Find the change points in `xs`.
+pub fn detect(xs: &[f64]) -> Vec<ChangePoint> {
+    let n = xs.len();
+    if n < 2 * MIN_SEGMENT { return vec![] }
+
+    let sigma = sigma(xs);
+    if sigma == 0.0 { return vec![] }
+
+    let mut changes = vec![];
+    let bounds = pelt(xs, 2.0 * sigma * sigma * (n as f64).ln());
+    for w in bounds.as_slice().windows(3) {
+        let (start, split, end) = (w[0], w[1], w[2]);
+        let before = median(xs.slice(start, split));
+        let after = median(xs.slice(split, end));
+
+        // a z-test on the difference in means, assuming the noise is
+        // the same on both sides.
+        let (n1, n2) = ((split - start) as f64, (end - split) as f64);
+        let z = (after - before).abs() / (sigma * (1.0 / n1 + 1.0 / n2).sqrt());
+        if (after - before).abs() >= MIN_SIGMAS * sigma {
+            changes.push(ChangePoint {
+                index: split,
+                before: before,
+                after: after,
+                confidence: erf(z / SQRT2),
+            });
+        }
+    }
+    changes
+}
+
+/// The segment boundaries (including 0 and `xs.len()`) that minimise
+/// the total squared deviation from each segment's mean plus
+/// `penalty` per segment.
+fn pelt(xs: &[f64], penalty: f64) -> Vec<uint> {
+    let n = xs.len();
+
+    // prefix sums, so the cost of any segment is O(1).
+    let mut s1 = Vec::with_capacity(n + 1);
+    let mut s2 = Vec::with_capacity(n + 1);
+    s1.push(0.0);
+    s2.push(0.0);
+    for &x in xs.iter() {
+        let (a, b) = (s1[s1.len() - 1], s2[s2.len() - 1]);
+        s1.push(a + x);
+        s2.push(b + x * x);
+    }
+    let (s1, s2) = (s1.as_slice(), s2.as_slice());
+
+    // best[t] is the minimal cost of xs[..t], and last[t] the start
+    // of the final segment in that optimum.
+    let mut best = Vec::from_elem(n + 1, 0.0);
+    let mut last = Vec::from_elem(n + 1, 0u);
+    *best.get_mut(0) = -penalty;
+    let mut candidates = vec![0u];
+
+    for t in range(MIN_SEGMENT, n + 1) {
+        let mut min = f64::INFINITY;
+        let mut arg = 0;
+        for &tau in candidates.iter() {
+            if t - tau < MIN_SEGMENT { continue }
+            let c = best[tau] + cost(s1, s2, tau, t) + penalty;
+            if c < min {
+                min = c;
+                arg = tau;
+            }
+        }
+        *best.get_mut(t) = min;
+        *last.get_mut(t) = arg;
+
+        // anything that can't beat the current optimum now never
+        // will (this is the "pruned" in PELT).
+        candidates.retain(|&tau| t - tau < MIN_SEGMENT || best[tau] + cost(s1, s2, tau, t) <= min);
+        if t + MIN_SEGMENT <= n {
+            candidates.push(t);
+        }
+    }
+
+    let mut bounds = vec![n];
+    let mut t = n;
+    while t > 0 {
+        t = last[t];
+        bounds.push(t);
+    }
+    bounds.reverse();
+    return bounds;
+
+    /// The squared deviation of `xs[s..e]` from its mean, given the
+    /// prefix sums of `xs` and `xs^2`.
+    fn cost(s1: &[f64], s2: &[f64], s: uint, e: uint) -> f64 {
+        let sum = s1[e] - s1[s];
+        (s2[e] - s2[s]) - sum * sum / (e - s) as f64
+    }
+}
+
+/// The error function, via Abramowitz & Stegun 7.1.26 (accurate to
+/// about 1e-7, which is plenty for a confidence).
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 +
+                                                         t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    if x < 0.0 { -y } else { y }
+}
@@ -0,0 +1,99 @@
+//! A Markdown report of a merge's performance impact, ready to paste
+//! into a comment on the PR. Nothing is posted: it's just written to
+//! out/comments/.
+
+use std::collections::TreeMap;
+use diff::{mod, Diff};
+use noise::Band;
+use pages::SITE;
+use perf_impact::{mod, Impact};
+use pull_requests;
+use {Summary, format_value};
+
+static COMMIT_URL: &'static str = "https://github.com/rust-lang/rust/commit/";
+/// The number of passes listed.
+static TOP_PASSES: uint = 5;
+
+/// The commit `target` refers to, either a PR number (which may have
+/// landed in a rollup) or a prefix of a hash.
+pub fn find<'a>(summary: &'a [Summary], target: &str) -> Option<&'a Summary> {
+    // a short hash could be all digits, but not this short.
+    match from_str::<uint>(target) {
+        Some(pr) if target.len() < 7 => {
+            summary.iter().find(|s| s.pull_request == Some(pr)).or_else(|| {
+                summary.iter().find(|s| {
+                    s.pull_requests.as_ref().map_or(false, |prs| prs.contains(&pr))
+                })
+            })
+        }
+        _ => summary.iter().find(|s| s.hash.as_slice().starts_with(target))
+    }
+}
+
+/// The report for `pr` (if it's about a PR) merging as `s`, whose
+/// diff from its parent is `d`, and `bands` is the baseline at `s`
+/// (from `perf_impact::baselines`).
+pub fn render(s: &Summary, pr: Option<uint>, d: &Diff, bands: &TreeMap<String, Band>) -> String {
+    let mut ret = match pr {
+        Some(pr) => match pull_requests::title(pr) {
+            Some(title) => format!("## Performance impact of #{}: {}\n\n", pr, title),
+            None => format!("## Performance impact of #{}\n\n", pr)
+        },
+        None => format!("## Performance impact of {}\n\n", s.hash.as_slice().slice_to(8))
+    };
+    match (pr, s.pull_request) {
+        (Some(pr), Some(merge)) if pr != merge => {
+            ret.push_str(format!("This landed in rollup #{}, so these numbers cover everything \
+                                  rolled up with it.\n\n", merge).as_slice());
+        }
+        _ => {}
+    }
+    ret.push_str(format!("Merged as [`{}`]({}{}), compared with its parent [`{}`]({}{}).\n\n",
+                         d.b.as_slice().slice_to(8), COMMIT_URL, d.b,
+                         d.a.as_slice().slice_to(8), COMMIT_URL, d.a).as_slice());
+
+    ret.push_str("| | Before | After | Change | Baseline |\n|---|---:|---:|---:|---:|\n");
+    for i in perf_impact::impacts(d.summary.as_slice(), bands).iter() {
+        ret.push_str(row(i, true).as_slice());
+    }
+    ret.push_str("\n");
+
+    let passes: Vec<Impact> = perf_impact::impacts(d.passes.as_slice(), bands).into_iter()
+        .filter(|i| i.delta != 0.0)
+        .take(TOP_PASSES)
+        .collect();
+    if !passes.is_empty() {
+        ret.push_str("### Passes that changed most\n\n\
+                      | Pass | Before | After | Change | Baseline |\n|---|---:|---:|---:|---:|\n");
+        for i in passes.iter() {
+            ret.push_str(row(i, false).as_slice());
+        }
+        ret.push_str("\n");
+    }
+
+    ret.push_str(format!("The baseline is the median ± MAD of the merges before this one. \
+                          Changes in **bold** are at least {} MADs, and left the number \
+                          at least that far from it. \
+                          [Full profile]({}/mem/commit/{}.html)\n",
+                         perf_impact::SIGNIFICANT, SITE, s.hash).as_slice());
+    ret
+}
+
+/// A table row. Pass names are shown as code, but `metric` names
+/// aren't.
+fn row(i: &Impact, metric: bool) -> String {
+    let name = i.name.as_slice();
+    let change = format!("{}{} ({})", if i.delta >= 0.0 { "+" } else { "" },
+                         format_value(name, i.delta), diff::percent(i.relative, i.after));
+    let change = if i.significant { format!("**{}**", change) } else { change };
+    let baseline = match (&i.baseline, i.mads) {
+        (&Some(ref b), Some(m)) => {
+            format!("{} ± {} ({:+.1f} MADs)",
+                    format_value(name, b.median), format_value(name, b.mad), m)
+        }
+        _ => "unknown".into_string()
+    };
+    let name = if metric { name.into_string() } else { format!("`{}`", name) };
+    format!("| {} | {} | {} | {} | {} |\n", name, format_value(i.name.as_slice(), i.before),
+            format_value(i.name.as_slice(), i.after), change, baseline)
+}
@@ -0,0 +1,334 @@
+//! Working out what a commit is from its commit_info.txt.
+
+use std::ascii::StrAsciiExt;
+
+/// Everything we know about a commit from its commit_info.txt.
+///
+/// There are two formats. The legacy one is exactly three lines:
+///
+/// ```text
+/// bors bors@rust-lang.org
+/// 1400000000
+/// auto merge of #1234 : user/branch, r=someone
+/// ```
+///
+/// The extended one is a git-like header of `key value` lines, a
+/// blank line, then the full commit message:
+///
+/// ```text
+/// author bors <bors@rust-lang.org>
+/// author-time 1400000000
+/// committer bors <bors@rust-lang.org>
+/// commit-time 1400000100
+/// parent 0123456789abcdef0123456789abcdef01234567
+/// parent 89abcdef0123456789abcdef0123456789abcdef
+///
+/// auto merge of #1234 : user/branch, r=someone
+///
+/// Some longer description.
+/// ```
+#[deriving(Clone, PartialEq, Show, Encodable, Decodable)]
+pub struct CommitInfo {
+    pub author: String,
+    /// The time used to place the commit on the summary, i.e. the
+    /// commit time if we know it, the author time otherwise.
+    pub timestamp: uint,
+    pub author_time: Option<uint>,
+    pub committer: Option<String>,
+    pub commit_time: Option<uint>,
+    /// Parent hashes, first parent first. Empty for the legacy format.
+    pub parents: Vec<String>,
+    /// The full message; just the first line for the legacy format.
+    pub message: String,
+}
+
+/// Parse the contents of a commit_info.txt in either format.
+pub fn parse(s: &str) -> Option<CommitInfo> {
+    parse_extended(s).or_else(|| parse_legacy(s))
+}
+
+fn parse_legacy(s: &str) -> Option<CommitInfo> {
+    let mut lines = s.lines();
+    match (lines.next(), lines.next().and_then(from_str), lines.next()) {
+        (Some(author), Some(timestamp), Some(summary)) => {
+            Some(CommitInfo {
+                author: author.into_string(),
+                timestamp: timestamp,
+                author_time: None,
+                committer: None,
+                commit_time: None,
+                parents: vec![],
+                message: summary.into_string(),
+            })
+        }
+        _ => None
+    }
+}
+
+fn parse_extended(s: &str) -> Option<CommitInfo> {
+    let mut author = None;
+    let mut author_time = None;
+    let mut committer = None;
+    let mut commit_time = None;
+    let mut parents = vec![];
+
+    let mut lines = s.lines();
+    for line in lines.by_ref() {
+        if line.is_empty() { break }
+
+        let (key, value) = match line.find(' ') {
+            Some(i) => (line.slice_to(i), line.slice_from(i + 1)),
+            None => return None
+        };
+        match key {
+            "author" => author = Some(value.into_string()),
+            "author-time" => match from_str(value) {
+                Some(t) => author_time = Some(t),
+                None => return None
+            },
+            "committer" => committer = Some(value.into_string()),
+            "commit-time" => match from_str(value) {
+                Some(t) => commit_time = Some(t),
+                None => return None
+            },
+            "parent" => parents.push(value.into_string()),
+            // not the extended format after all
+            _ => return None
+        }
+    }
+    let message = lines.collect::<Vec<&str>>().connect("\n");
+
+    match (author, commit_time.or(author_time)) {
+        (Some(author), Some(timestamp)) => {
+            Some(CommitInfo {
+                author: author,
+                timestamp: timestamp,
+                author_time: author_time,
+                committer: committer,
+                commit_time: commit_time,
+                parents: parents,
+                message: message,
+            })
+        }
+        _ => None
+    }
+}
+
+/// The pull requests a commit merged.
+#[deriving(Clone, PartialEq, Show)]
+pub struct PullRequests {
+    /// The PR that this commit is the merge of, e.g. the rollup PR
+    /// itself for a rollup.
+    pub merge: Option<uint>,
+    /// The PRs whose changes actually landed in this commit: the
+    /// rolled-up ones for a rollup, or just `merge` otherwise.
+    pub constituents: Vec<uint>,
+}
+
+/// Extract the pull request(s) from a commit message (or just its
+/// first line). Understands:
+///
+/// - old bors: `auto merge of #1234 : user/branch, r=someone`
+/// - homu: `Auto merge of #1234 - user:branch, r=someone`
+/// - rollup members: `Rollup merge of #1234 - user:branch, r=someone`
+/// - rollups (from a `…/rollup` or `…:rollup` branch, or saying
+///   `Rollup of N pull requests`) list their members in the body as ` - #1234 (title)`,
+///   or as `- Successful merges: #1234, #1235`, followed by the ones
+///   that didn't land under `Failed merges`.
+/// - bors-ng: `Merge #1234 #1235`
+/// - GitHub: `Merge pull request #1234 from user/branch`
+/// - GitHub merge queue/squash: `Some title (#1234)`
+pub fn pull_requests(message: &str) -> PullRequests {
+    let mut lines = message.lines();
+    let summary = lines.next().unwrap_or("").trim();
+    let lower = summary.to_ascii_lower();
+    let lower = lower.as_slice();
+
+    let mut constituents = vec![];
+    let prefixes = ["auto merge of #", "rollup merge of #", "merge pull request #"];
+    let mut merge = prefixes.iter()
+        .find(|p| lower.starts_with(**p))
+        .and_then(|p| leading_number(summary.slice_from(p.len())));
+
+    if merge.is_none() && lower.starts_with("merge #") {
+        // bors-ng merges a batch at once, and lists them all.
+        constituents = numbers(summary.slice_from("merge ".len()));
+        if constituents.len() == 1 {
+            merge = Some(constituents[0]);
+        }
+    }
+    if merge.is_none() && constituents.is_empty() && summary.ends_with(")") {
+        merge = summary.rfind('(')
+            .map(|i| summary.slice_from(i + 1))
+            .and_then(|s| if s.starts_with("#") { leading_number(s.slice_from(1)) } else { None })
+    }
+
+    // an ordinary PR's description can have a list of PRs too, so
+    // only a rollup's is the list of what landed.
+    let rollup = lower.contains("/rollup") || lower.contains(":rollup") || message.lines().skip(1).any(|l| {
+        l.trim().to_ascii_lower().as_slice().starts_with("rollup of ")
+    });
+    if constituents.is_empty() && rollup {
+        for l in lines.map(|l| l.trim()) {
+            let bare = if l.starts_with("- ") { l.slice_from(2) } else { l };
+            let bare_lower = bare.to_ascii_lower();
+            let bare_lower = bare_lower.as_slice();
+            if bare_lower.starts_with("failed merges") {
+                break
+            } else if bare_lower.starts_with("successful merges:") {
+                constituents.extend(numbers(bare.slice_from("successful merges:".len())).into_iter());
+            } else if l.starts_with("- #") {
+                constituents.extend(leading_number(l.slice_from(3)).into_iter());
+            }
+        }
+    }
+    if constituents.is_empty() {
+        constituents.extend(merge.into_iter());
+    }
+
+    PullRequests { merge: merge, constituents: constituents }
+}
+
+/// The numbers of the `#1234`s among the words of `s`.
+fn numbers(s: &str) -> Vec<uint> {
+    s.words()
+        .filter_map(|w| if w.starts_with("#") { leading_number(w.slice_from(1)) } else { None })
+        .collect()
+}
+
+/// Read the number at the start of `s`, if there is one.
+fn leading_number(s: &str) -> Option<uint> {
+    let end = s.find(|c: char| !c.is_digit()).unwrap_or(s.len());
+    from_str(s.slice_to(end))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, pull_requests, CommitInfo, PullRequests};
+
+    fn prs(merge: Option<uint>, constituents: Vec<uint>) -> PullRequests {
+        PullRequests { merge: merge, constituents: constituents }
+    }
+
+    #[test]
+    fn legacy() {
+        let ci = parse("bors bors@rust-lang.org\n1400000000\nauto merge of #1234 : a/b, r=c\n");
+        assert_eq!(ci, Some(CommitInfo {
+            author: "bors bors@rust-lang.org".into_string(),
+            timestamp: 1400000000,
+            author_time: None,
+            committer: None,
+            commit_time: None,
+            parents: vec![],
+            message: "auto merge of #1234 : a/b, r=c".into_string(),
+        }));
+    }
+
+    #[test]
+    fn extended() {
+        let ci = parse("author bors <bors@rust-lang.org>\n\
+                        author-time 1400000000\n\
+                        committer bors <bors@rust-lang.org>\n\
+                        commit-time 1400000100\n\
+                        parent 0123\n\
+                        parent 4567\n\
+                        \n\
+                        auto merge of #1234 : a/b, r=c\n\
+                        \n\
+                        Some longer description.").unwrap();
+        assert_eq!(ci.author.as_slice(), "bors <bors@rust-lang.org>");
+        assert_eq!(ci.timestamp, 1400000100);
+        assert_eq!(ci.author_time, Some(1400000000));
+        assert_eq!(ci.commit_time, Some(1400000100));
+        assert_eq!(ci.parents, vec!["0123".into_string(), "4567".into_string()]);
+        assert_eq!(ci.message.as_slice(),
+                   "auto merge of #1234 : a/b, r=c\n\nSome longer description.");
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(parse("bors\nyesterday\nauto merge of #1234"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn old_bors() {
+        assert_eq!(pull_requests("auto merge of #1234 : user/branch, r=someone"),
+                   prs(Some(1234), vec![1234]));
+    }
+
+    #[test]
+    fn homu() {
+        assert_eq!(pull_requests("Auto merge of #1234 - user:branch, r=someone"),
+                   prs(Some(1234), vec![1234]));
+    }
+
+    #[test]
+    fn rollup_member() {
+        assert_eq!(pull_requests("Rollup merge of #1234 - user:branch, r=someone"),
+                   prs(Some(1234), vec![1234]));
+    }
+
+    #[test]
+    fn old_bors_rollup() {
+        assert_eq!(pull_requests("auto merge of #1240 : user/rollup, r=someone\n\
+                                  \n\
+                                  - #1234 (first)\n\
+                                  - #1235 (second)"),
+                   prs(Some(1240), vec![1234, 1235]));
+    }
+
+    #[test]
+    fn homu_rollup() {
+        assert_eq!(pull_requests("Auto merge of #1240 - user:rollup, r=someone\n\
+                                  \n\
+                                  - Successful merges: #1234, #1235\n\
+                                  - Failed merges: #1236"),
+                   prs(Some(1240), vec![1234, 1235]));
+    }
+
+    #[test]
+    fn rollup_by_body() {
+        assert_eq!(pull_requests("Auto merge of #1240 - user:batch, r=someone\n\
+                                  \n\
+                                  Rollup of 2 pull requests\n\
+                                  \n\
+                                  - #1234 (first)\n\
+                                  - #1235 (second)\n\
+                                  Failed merges:\n\
+                                  \n\
+                                  - #1236 (third)"),
+                   prs(Some(1240), vec![1234, 1235]));
+    }
+
+    #[test]
+    fn not_a_rollup() {
+        // an ordinary PR listing the ones it follows on from.
+        assert_eq!(pull_requests("Auto merge of #1240 - user:branch, r=someone\n\
+                                  \n\
+                                  - #1234 (first)"),
+                   prs(Some(1240), vec![1240]));
+    }
+
+    #[test]
+    fn bors_ng() {
+        assert_eq!(pull_requests("Merge #1234"), prs(Some(1234), vec![1234]));
+        assert_eq!(pull_requests("Merge #1234 #1235"), prs(None, vec![1234, 1235]));
+    }
+
+    #[test]
+    fn github() {
+        assert_eq!(pull_requests("Merge pull request #1234 from user/branch"),
+                   prs(Some(1234), vec![1234]));
+    }
+
+    #[test]
+    fn squash() {
+        assert_eq!(pull_requests("Some title (#1234)"), prs(Some(1234), vec![1234]));
+    }
+
+    #[test]
+    fn no_pr() {
+        assert_eq!(pull_requests("Fix a typo"), prs(None, vec![]));
+    }
+}
@@ -0,0 +1,185 @@
+//! Comparing two processed commits.
+
+use serialize::Decodable;
+use serialize::json::{mod, Json};
+use align;
+use pass_history;
+use resample::linear;
+use {Summary, MIB};
+
+/// The change in one number between two commits.
+#[deriving(Encodable, Clone)]
+pub struct Delta {
+    pub name: String,
+    pub a: f64,
+    pub b: f64,
+    /// b - a
+    pub delta: f64,
+    /// (b - a) / a, or None if a is 0, e.g. a pass only b ran.
+    pub relative: Option<f64>,
+}
+
+impl Delta {
+    fn new(name: &str, a: f64, b: f64) -> Delta {
+        let relative = if a == 0.0 { None } else { Some((b - a) / a) };
+        Delta { name: name.into_string(), a: a, b: b, delta: b - a, relative: relative }
+    }
+}
+
+/// A relative change for people: `+1.2%`, or `new` if it was 0
+/// before and isn't now, or `–` if it's still 0.
+pub fn percent(relative: Option<f64>, after: f64) -> String {
+    match relative {
+        Some(r) => format!("{:+.1f}%", 100.0 * r),
+        None if after != 0.0 => "new".into_string(),
+        None => "–".into_string()
+    }
+}
+
+/// Everything that changed between commits `a` and `b`.
+#[deriving(Encodable)]
+pub struct Diff {
+    pub a: String,
+    pub b: String,
+    /// max_memory, cpu_time and elapsed, where both commits have them.
+    pub summary: Vec<Delta>,
+    /// Passes, biggest change first. A pass that only one commit ran
+    /// counts as taking 0s in the other.
+    pub passes: Vec<Delta>,
+    /// (time, b's memory - a's memory), at every time either was
+    /// sampled while both were running.
+    pub memory: Vec<(f64, f64)>,
+    /// (a's time, b's memory - a's memory) after warping b's time
+    /// axis onto a's, so that a slower early pass doesn't make
+    /// everything after it look different.
+    pub aligned_memory: Vec<(f64, f64)>,
+}
+
+/// The parts of an out/<hash>.json we need for a diff.
+struct Side<'a> {
+    summary: Summary,
+    json: &'a Json,
+}
+
+fn side<'a>(hash: &str, json: &'a Json) -> Side<'a> {
+    let summary = json.find(&"summary".into_string())
+        .expect(format!("{}'s output has no summary", hash).as_slice())
+        .clone();
+    let summary = Decodable::decode(&mut json::Decoder::new(summary))
+        .ok().expect(format!("{}'s summary is invalid", hash).as_slice());
+    Side { summary: summary, json: json }
+}
+
+/// Compare the outputs `a` and `b` of two commits.
+pub fn diff(a_hash: &str, a: &Json, b_hash: &str, b: &Json) -> Diff {
+    let a = side(a_hash, a);
+    let b = side(b_hash, b);
+
+    let mut summary = vec![Delta::new("max_memory", a.summary.max_memory, b.summary.max_memory)];
+    let pairs = [("cpu_time", a.summary.cpu_time, b.summary.cpu_time),
+                 ("elapsed", a.summary.elapsed, b.summary.elapsed)];
+    for &(name, x, y) in pairs.iter() {
+        match (x, y) {
+            (Some(x), Some(y)) => summary.push(Delta::new(name, x, y)),
+            _ => {}
+        }
+    }
+
+    let a_passes = pass_history::pass_timing(a.json);
+    let b_passes = pass_history::pass_timing(b.json);
+    let mut passes: Vec<Delta> = a_passes.iter().map(|(name, &x)| {
+        Delta::new(name.as_slice(), x, b_passes.find(name).map(|y| *y).unwrap_or(0.0))
+    }).collect();
+    passes.extend(b_passes.iter().filter(|&(name, _)| !a_passes.contains_key(name)).map(|(name, &y)| {
+        Delta::new(name.as_slice(), 0.0, y)
+    }));
+    passes.sort_by(|x, y| y.delta.abs().partial_cmp(&x.delta.abs()).unwrap_or(Equal));
+
+    let (a_mem, b_mem) = (memory_data(a.json), memory_data(b.json));
+    let aligned = align::aligned_difference(a_mem.as_slice(),
+                                            pass_history::pass_list(a.json).as_slice(),
+                                            b_mem.as_slice(),
+                                            pass_history::pass_list(b.json).as_slice());
+    Diff {
+        a: a_hash.into_string(),
+        b: b_hash.into_string(),
+        summary: summary,
+        passes: passes,
+        memory: memory_diff(a_mem.as_slice(), b_mem.as_slice()),
+        aligned_memory: aligned,
+    }
+}
+
+/// The (time, bytes) pairs of an out/<hash>.json.
+pub fn memory_data(out: &Json) -> Vec<(f64, f64)> {
+    let points = match out.find(&"memory_data".into_string()).and_then(|m| m.as_list()) {
+        Some(p) => p,
+        None => return vec![]
+    };
+    points.iter().filter_map(|p| {
+        p.as_list().and_then(|p| {
+            if p.len() != 2 { return None }
+            match (p[0].as_number(), p[1].as_number()) {
+                (Some(t), Some(m)) => Some((t, m)),
+                _ => None
+            }
+        })
+    }).collect()
+}
+
+/// b - a, at each time either was sampled while both were running.
+fn memory_diff(a: &[(f64, f64)], b: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    if a.is_empty() || b.is_empty() { return vec![] }
+    let ((a_start, _), (b_start, _)) = (a[0], b[0]);
+    let ((a_end, _), (b_end, _)) = (a[a.len() - 1], b[b.len() - 1]);
+    let (start, end) = (a_start.max(b_start), a_end.min(b_end));
+
+    let mut times: Vec<f64> = a.iter().chain(b.iter())
+        .map(|&(t, _)| t)
+        .filter(|&t| start <= t && t <= end)
+        .collect();
+    times.sort_by(|x, y| x.partial_cmp(y).unwrap_or(Equal));
+    times.dedup();
+
+    times.into_iter().map(|t| (t, linear(b, t) - linear(a, t))).collect()
+}
+
+/// Write `d` out for a person to read.
+pub fn print_text(d: &Diff) {
+    println!("{} -> {}", d.a, d.b);
+    println!("");
+    for delta in d.summary.iter().chain(d.passes.iter()) {
+        if delta.name.as_slice() == "max_memory" {
+            println!("{:<40} {:>10.1f} MiB -> {:>10.1f} MiB  {:>+10.1f} MiB ({})",
+                     delta.name, delta.a / MIB, delta.b / MIB, delta.delta / MIB,
+                     percent(delta.relative, delta.b));
+        } else {
+            println!("{:<40} {:>10.3f} s   -> {:>10.3f} s    {:>+10.3f} s   ({})",
+                     delta.name, delta.a, delta.b, delta.delta, percent(delta.relative, delta.b));
+        }
+    }
+    println!("");
+    let curves = [("largest memory difference", d.memory.as_slice()),
+                  ("largest aligned memory difference", d.aligned_memory.as_slice())];
+    for &(label, curve) in curves.iter() {
+        match largest(curve) {
+            Some((t, m)) => println!("{}: {:+.1f} MiB at {:.2f} s", label, m / MIB, t),
+            None => {}
+        }
+    }
+}
+
+/// Write `d` out as JSON.
+pub fn print_json(d: &Diff) {
+    println!("{}", json::encode(d));
+}
+
+/// The point with the largest magnitude.
+fn largest(xs: &[(f64, f64)]) -> Option<(f64, f64)> {
+    xs.iter().fold(None, |best: Option<(f64, f64)>, &(t, m)| {
+        match best {
+            Some((_, bm)) if bm.abs() >= m.abs() => best,
+            _ => Some((t, m))
+        }
+    })
+}
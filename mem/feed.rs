@@ -0,0 +1,79 @@
+//! An Atom feed of the changes we've found in the summary metrics
+//! and the passes, written to out/feed.xml, for anyone who'd like to
+//! hear about them.
+
+use std::collections::HashMap;
+use time;
+use diff;
+use pages::SITE;
+use pull_requests;
+use regressions::Regression;
+use svg::escape;
+use {Summary, format_value};
+
+/// Only this many of the most recent changes are in the feed.
+static MAX_ENTRIES: uint = 50;
+
+/// The feed of `changes` along `summary`, newest first.
+pub fn feed(summary: &[Summary], changes: &[Regression]) -> String {
+    let timestamps: HashMap<&str, uint> = summary.iter()
+        .map(|s| (s.hash.as_slice(), s.timestamp))
+        .collect();
+    let mut changes: Vec<(uint, &Regression)> = changes.iter()
+        .filter_map(|r| timestamps.find(&r.hash.as_slice()).map(|&t| (t, r)))
+        .collect();
+    // newest first, then by metric so the order is stable.
+    changes.sort_by(|&(t1, r1), &(t2, r2)| {
+        (t2, &r2.metric).cmp(&(t1, &r1.metric))
+    });
+    changes.truncate(MAX_ENTRIES);
+
+    // the newest change rather than the current time, so an update
+    // that finds nothing new doesn't change the file.
+    let updated = changes.iter().next().map(|&(t, _)| t).unwrap_or(0);
+    let mut ret = format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+                           <feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+                           <title>Is Rust Slim Yet?</title>\n\
+                           <subtitle>Changes in rustc's memory use and pass timings</subtitle>\n\
+                           <link href=\"{0}/mem/\"/>\n\
+                           <link rel=\"self\" href=\"{0}/mem/out/feed.xml\"/>\n\
+                           <id>{0}/mem/out/feed.xml</id>\n\
+                           <author><name>Is Rust Slim Yet?</name></author>\n\
+                           <updated>{1}</updated>\n",
+                          SITE, rfc3339(updated));
+    for &(t, r) in changes.iter() {
+        ret.push_str(entry(t, r).as_slice());
+    }
+    ret.push_str("</feed>\n");
+    ret
+}
+
+fn entry(timestamp: uint, r: &Regression) -> String {
+    let kind = if r.regression { "Regression" } else { "Improvement" };
+    let page = format!("{}/mem/commit/{}.html", SITE, r.hash);
+    let landed = match r.pull_request {
+        Some(pr) => match pull_requests::title(pr) {
+            Some(title) => format!("#{}: {}", pr, title),
+            None => format!("#{}", pr)
+        },
+        None => format!("commit {}", r.hash.as_slice().slice_to(8))
+    };
+    let summary = format!("{} went from {} to {} ({}, {:.0f}% confidence) with {}.",
+                          r.metric, format_value(r.metric.as_slice(), r.before),
+                          format_value(r.metric.as_slice(), r.after),
+                          diff::percent(r.change, r.after), 100.0 * r.confidence, landed);
+    format!("<entry>\n\
+             <title>{}: {}</title>\n\
+             <link href=\"{}\"/>\n\
+             <id>{}#{}</id>\n\
+             <updated>{}</updated>\n\
+             <summary>{}</summary>\n\
+             </entry>\n",
+            kind, escape(r.to_string().as_slice()), page, page,
+            escape(r.metric.replace(" ", "-").as_slice()),
+            rfc3339(timestamp), escape(summary.as_slice()))
+}
+
+fn rfc3339(timestamp: uint) -> String {
+    time::at_utc(time::Timespec::new(timestamp as i64, 0)).rfc3339()
+}
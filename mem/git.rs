@@ -0,0 +1,94 @@
+//! Asking a local clone of rust-lang/rust about commits.
+
+use std::collections::HashMap;
+use std::io::process::Command;
+use commit_info::CommitInfo;
+
+/// Run git inside `repo`, returning its stdout if it succeeded.
+fn git(repo: &Path, args: &[&str]) -> Option<String> {
+    match Command::new("git").cwd(repo).args(args).output() {
+        Ok(ref out) if out.status.success() => String::from_utf8(out.output.clone()).ok(),
+        _ => None
+    }
+}
+
+/// The commit `rev` is (the first line of the list, i.e. the head of
+/// the chain), and the first parent of every commit on its
+/// first-parent chain.
+pub fn first_parents(repo: &Path, rev: &str) -> (Option<String>, HashMap<String, String>) {
+    let out = git(repo, ["rev-list", "--first-parent", "--parents", rev])
+        .unwrap_or_else(|| {
+            println!("warning: couldn't list the history of {} in {}", rev, repo.display());
+            String::new()
+        });
+    let head = out.as_slice().lines().next()
+        .and_then(|l| l.words().next())
+        .map(|h| h.into_string());
+    let parents = out.as_slice().lines().filter_map(|l| {
+        let mut hashes = l.words();
+        match (hashes.next(), hashes.next()) {
+            (Some(h), Some(p)) => Some((h.into_string(), p.into_string())),
+            _ => None
+        }
+    }).collect();
+    (head, parents)
+}
+
+/// Look up `hash` in the object database, or None if the clone
+/// doesn't have it (or it isn't a commit).
+pub fn commit_info(repo: &Path, hash: &str) -> Option<CommitInfo> {
+    git(repo, ["cat-file", "commit", hash]).and_then(|raw| parse_commit(raw.as_slice()))
+}
+
+/// Parse a raw commit object, as printed by `git cat-file commit`.
+fn parse_commit(s: &str) -> Option<CommitInfo> {
+    let mut author = None;
+    let mut committer = None;
+    let mut parents = vec![];
+
+    let mut lines = s.lines();
+    for line in lines.by_ref() {
+        if line.is_empty() { break }
+        // continuation of a multi-line header, e.g. gpgsig
+        if line.starts_with(" ") { continue }
+
+        let (key, value) = match line.find(' ') {
+            Some(i) => (line.slice_to(i), line.slice_from(i + 1)),
+            None => continue
+        };
+        match key {
+            "parent" => parents.push(value.into_string()),
+            "author" => author = person(value),
+            "committer" => committer = person(value),
+            _ => {}
+        }
+    }
+    let message = lines.collect::<Vec<&str>>().connect("\n");
+
+    match (author, committer) {
+        (Some((author, author_time)), Some((committer, commit_time))) => {
+            Some(CommitInfo {
+                author: author,
+                timestamp: commit_time,
+                author_time: Some(author_time),
+                committer: Some(committer),
+                commit_time: Some(commit_time),
+                parents: parents,
+                message: message,
+            })
+        }
+        _ => None
+    }
+
+    /// Split `Name <email> 1400000000 +0000` into the name/email and
+    /// the time.
+    fn person(s: &str) -> Option<(String, uint)> {
+        let mut parts = s.rsplitn(2, ' ');
+        let _tz = parts.next();
+        let time = parts.next().and_then(from_str);
+        match (parts.next(), time) {
+            (Some(who), Some(time)) => Some((who.into_string(), time)),
+            _ => None
+        }
+    }
+}
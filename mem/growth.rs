@@ -0,0 +1,150 @@
+//! How fast memory grows through a run, to catch things like an
+//! arena that is never freed making a phase that used to be flat
+//! climb steadily.
+
+use resample::linear;
+use pass_memory::PassMemory;
+use serialize::Decodable;
+use serialize::json::{mod, Json};
+
+/// The trace is cut into this many equal pieces for `windows`.
+static WINDOWS: uint = 20;
+/// Passes shorter than this (seconds) don't have a meaningful slope.
+static MIN_DURATION: f64 = 0.5;
+/// A pass's slope has changed significantly if it moved by more than
+/// this many bytes/s...
+static MIN_CHANGE: f64 = 10.0 * 1024.0 * 1024.0;
+/// ... and by more than this fraction of the baseline's slope.
+static MIN_RELATIVE_CHANGE: f64 = 0.5;
+
+/// The growth rate (bytes/s) over part of a run.
+#[deriving(Encodable, Decodable, Clone, Show)]
+pub struct Slope {
+    /// A pass name, or empty for a window of the trace.
+    pub name: String,
+    pub start: f64,
+    pub end: f64,
+    pub slope: f64,
+}
+
+/// A pass whose growth rate is noticeably different to the baseline's.
+#[deriving(Encodable, Decodable, Clone, Show)]
+pub struct SlopeChange {
+    pub name: String,
+    pub baseline: f64,
+    pub slope: f64,
+}
+
+#[deriving(Encodable, Decodable, Clone, Show)]
+pub struct Growth {
+    /// Equal slices of the whole trace.
+    pub windows: Vec<Slope>,
+    pub passes: Vec<Slope>,
+    /// The commit the passes were compared against (the one before
+    /// it, see `order::parents`), once that has been processed.
+    pub baseline: Option<String>,
+    pub changes: Vec<SlopeChange>,
+}
+
+/// The growth rates of `memory` over equal windows and over each of
+/// `passes`.
+pub fn growth(memory: &[(f64, f64)], passes: &[PassMemory]) -> Growth {
+    let windows = if memory.len() < 2 {
+        vec![]
+    } else {
+        let ((start, _), (end, _)) = (memory[0], memory[memory.len() - 1]);
+        let width = (end - start) / WINDOWS as f64;
+        range(0, WINDOWS).map(|i| {
+            let (s, e) = (start + width * i as f64, start + width * (i + 1) as f64);
+            Slope { name: String::new(), start: s, end: e, slope: slope(memory, s, e) }
+        }).collect()
+    };
+    Growth {
+        windows: windows,
+        passes: pass_slopes(memory, passes),
+        baseline: None,
+        changes: vec![],
+    }
+}
+
+/// `out`, an out/<hash>.json, with its growth compared with that of
+/// the commit `hash`, whose output is `baseline`. None if that needn't
+/// (or can't) be done, so there's nothing to write.
+pub fn update(out: &Json, hash: &str, baseline: &Json) -> Option<Json> {
+    let mut g: Growth = match out.find(&"growth".into_string())
+        .and_then(|g| Decodable::decode(&mut json::Decoder::new(g.clone())).ok()) {
+        Some(g) => g,
+        // processed before growth was recorded.
+        None => return None
+    };
+    if g.baseline.as_ref().map(|b| b.as_slice()) == Some(hash) { return None }
+
+    if !compare(&mut g, hash, baseline) { return None }
+    match out.clone() {
+        json::Object(mut o) => {
+            let encoded = json::from_str(json::encode(&g).as_slice()).unwrap();
+            o.insert("growth".into_string(), encoded);
+            Some(json::Object(o))
+        }
+        _ => None
+    }
+}
+
+/// Compare the pass slopes of `g` with those of the commit `hash`,
+/// whose out/<hash>.json is `baseline`. Its stored slopes are used,
+/// since they came from the raw trace like `g`'s: the simplified one
+/// in the output gives different slopes. Returns false if it has
+/// none to compare with.
+fn compare(g: &mut Growth, hash: &str, baseline: &Json) -> bool {
+    let base: Vec<Slope> = match baseline.find(&"growth".into_string())
+        .and_then(|g| g.find(&"passes".into_string()))
+        .and_then(|p| Decodable::decode(&mut json::Decoder::new(p.clone())).ok()) {
+        Some(base) => base,
+        // processed before growth was recorded.
+        None => return false
+    };
+
+    g.baseline = Some(hash.into_string());
+    g.changes = g.passes.iter().filter_map(|p| {
+        base.iter().find(|b| b.name == p.name).and_then(|b| {
+            let change = (p.slope - b.slope).abs();
+            if change > MIN_CHANGE && change > MIN_RELATIVE_CHANGE * b.slope.abs() {
+                Some(SlopeChange { name: p.name.clone(), baseline: b.slope, slope: p.slope })
+            } else {
+                None
+            }
+        })
+    }).collect();
+    true
+}
+
+fn pass_slopes(memory: &[(f64, f64)], passes: &[PassMemory]) -> Vec<Slope> {
+    passes.iter().filter(|p| p.end - p.start >= MIN_DURATION).map(|p| {
+        Slope {
+            name: p.name.clone(),
+            start: p.start,
+            end: p.end,
+            slope: slope(memory, p.start, p.end),
+        }
+    }).collect()
+}
+
+/// The least-squares slope of the samples of `xs` between `start`
+/// and `end`, or just end-to-end if there are too few of them.
+fn slope(xs: &[(f64, f64)], start: f64, end: f64) -> f64 {
+    let inside: Vec<(f64, f64)> = xs.iter()
+        .filter(|&&(t, _)| start <= t && t <= end)
+        .map(|&p| p)
+        .collect();
+    if inside.len() < 3 {
+        return (linear(xs, end) - linear(xs, start)) / (end - start);
+    }
+
+    let n = inside.len() as f64;
+    let mean_t = inside.iter().fold(0.0, |a, &(t, _)| a + t) / n;
+    let mean_m = inside.iter().fold(0.0, |a, &(_, m)| a + m) / n;
+    let (num, den) = inside.iter().fold((0.0, 0.0), |(num, den), &(t, m)| {
+        (num + (t - mean_t) * (m - mean_m), den + (t - mean_t) * (t - mean_t))
+    });
+    if den == 0.0 { 0.0 } else { num / den }
+}
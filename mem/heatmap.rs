@@ -0,0 +1,62 @@
+//! A commit × time matrix of memory usage, to show how the shape of
+//! the whole profile drifts over many merges.
+
+use serialize::json::Json;
+use diff;
+use resample::{mod, Grid, Linear, Percent, Seconds};
+use {Summary, MIB};
+
+#[deriving(Encodable)]
+pub struct Heatmap {
+    /// "percent" or "seconds"
+    pub axis: String,
+    /// The time of each column.
+    pub columns: Vec<f64>,
+    /// The commit of each row, in summary order.
+    pub hashes: Vec<String>,
+    /// Memory in MiB (rounded, to keep the file small), or null after
+    /// the commit's trace ended.
+    pub rows: Vec<Vec<Option<uint>>>,
+}
+
+/// Build the heatmap of the commits of `summary` (which should be in
+/// order), using `load` to look up out/<hash>.json.
+pub fn heatmap<'a>(summary: &[Summary], grid: Grid, load: |&str| -> Option<&'a Json>) -> Heatmap {
+    let mut hashes = vec![];
+    let mut traces = vec![];
+    for s in summary.iter() {
+        let trace = load(s.hash.as_slice()).map(|out| diff::memory_data(out));
+        match trace {
+            Some(t) => if !t.is_empty() {
+                hashes.push(s.hash.clone());
+                traces.push(t);
+            },
+            None => {}
+        }
+    }
+
+    let (axis, columns) = match grid {
+        Percent(n) => ("percent", resample::evenly(0.0, 100.0, n)),
+        Seconds(every) => {
+            let end = traces.iter().fold(0.0, |a, t| {
+                let (e, _) = t[t.len() - 1];
+                a.max(e)
+            });
+            ("seconds", range(0, (end / every).floor() as uint + 1).map(|i| every * i as f64).collect())
+        }
+    };
+
+    let rows = traces.iter().map(|t| {
+        let cells = resample::resample(t.as_slice(), grid, Linear);
+        let mut row: Vec<Option<uint>> = cells.iter()
+            .map(|&(_, m)| Some((m / MIB).round() as uint))
+            .collect();
+        row.truncate(columns.len());
+        while row.len() < columns.len() {
+            row.push(None);
+        }
+        row
+    }).collect();
+
+    Heatmap { axis: axis.into_string(), columns: columns, hashes: hashes, rows: rows }
+}
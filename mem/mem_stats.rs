@@ -0,0 +1,65 @@
+//! Statistics of a whole memory trace, rather than just its peak.
+//!
+//! These treat each sample as holding until the next one, so they're
+//! weighted by time rather than by the (irregular) sampling.
+
+/// Time-weighted statistics of a memory trace.
+#[deriving(Encodable, Decodable, Clone, PartialEq, Show)]
+pub struct MemoryStats {
+    /// bytes
+    pub mean_memory: f64,
+    /// memory × time, in GB·s
+    pub memory_integral: f64,
+    /// seconds spent above 50%, 75% and 90% of the peak
+    pub time_above_50: f64,
+    pub time_above_75: f64,
+    pub time_above_90: f64,
+    /// bytes, such that the given fraction of the time was spent at or
+    /// below it
+    pub memory_p50: f64,
+    pub memory_p90: f64,
+    pub memory_p99: f64,
+}
+
+/// Compute the statistics of the raw (not simplified) trace `xs`,
+/// or None if it doesn't cover any time.
+pub fn stats(xs: &[(f64, f64)]) -> Option<MemoryStats> {
+    // (memory, how long it was held)
+    let mut held: Vec<(f64, f64)> = xs.windows(2).map(|w| {
+        let ((t0, m), (t1, _)) = (w[0], w[1]);
+        (m, t1 - t0)
+    }).filter(|&(_, dt)| dt > 0.0).collect();
+
+    let total = held.iter().fold(0.0, |a, &(_, dt)| a + dt);
+    if total <= 0.0 { return None }
+
+    let integral = held.iter().fold(0.0, |a, &(m, dt)| a + m * dt);
+    let peak = xs.iter().fold(0.0, |a, &(_, m)| a.max(m));
+    let (above_50, above_75, above_90) = {
+        let above = |fraction: f64| {
+            held.iter().filter(|&&(m, _)| m > fraction * peak).fold(0.0, |a, &(_, dt)| a + dt)
+        };
+        (above(0.5), above(0.75), above(0.9))
+    };
+
+    held.sort_by(|&(a, _), &(b, _)| a.partial_cmp(&b).unwrap_or(Equal));
+    let percentile = |p: f64| {
+        let mut elapsed = 0.0;
+        for &(m, dt) in held.iter() {
+            elapsed += dt;
+            if elapsed >= p * total { return m }
+        }
+        peak
+    };
+
+    Some(MemoryStats {
+        mean_memory: integral / total,
+        memory_integral: integral / 1e9,
+        time_above_50: above_50,
+        time_above_75: above_75,
+        time_above_90: above_90,
+        memory_p50: percentile(0.5),
+        memory_p90: percentile(0.9),
+        memory_p99: percentile(0.99),
+    })
+}
@@ -0,0 +1,106 @@
+//! Robust statistics for telling real changes from run-to-run noise.
+
+use std::collections::{HashMap, TreeMap};
+use std::f64;
+use std::f64::consts::SQRT2;
+use {Summary, METRICS};
+
+/// The usual level of a metric and how much it wobbles.
+#[deriving(Encodable, Decodable, Clone, PartialEq, Show)]
+pub struct Band {
+    pub median: f64,
+    /// median absolute deviation from `median`.
+    pub mad: f64,
+}
+
+impl Band {
+    /// How many MADs `x` is from the median (signed, so positive
+    /// means larger).
+    pub fn mads(&self, x: f64) -> f64 {
+        if self.mad == 0.0 {
+            if x == self.median { 0.0 } else { (x - self.median) * f64::INFINITY }
+        } else {
+            (x - self.median) / self.mad
+        }
+    }
+}
+
+/// The baseline of each metric at one commit, for drawing a noise
+/// band alongside the summary.
+#[deriving(Encodable, Decodable, Clone)]
+pub struct Baseline {
+    pub hash: String,
+    pub sequence: Option<uint>,
+    /// metric name -> band, for the metrics with enough history.
+    pub bands: TreeMap<String, Band>,
+}
+
+/// The rolling baseline of every metric along `summary`, which
+/// should be in order.
+pub fn baselines(summary: &[Summary]) -> Vec<Baseline> {
+    let mut bands = HashMap::new();
+    for &metric in METRICS.iter() {
+        let points: Vec<(&Summary, f64)> = summary.iter()
+            .filter_map(|s| s.metric(metric).map(|x| (s, x)))
+            .collect();
+        let xs: Vec<f64> = points.iter().map(|&(_, x)| x).collect();
+        for (&(s, _), band) in points.iter().zip(rolling(xs.as_slice()).into_iter()) {
+            match band {
+                Some(b) => {
+                    bands.find_or_insert_with(s.hash.clone(), |_| TreeMap::new())
+                        .insert(metric.into_string(), b);
+                }
+                None => {}
+            }
+        }
+    }
+
+    summary.iter().map(|s| {
+        Baseline {
+            hash: s.hash.clone(),
+            sequence: s.sequence,
+            bands: bands.pop(&s.hash).unwrap_or_else(|| TreeMap::new()),
+        }
+    }).collect()
+}
+
+/// The number of preceding points that make up a baseline.
+static WINDOW: uint = 20;
+/// The fewest preceding points we'll compute a baseline from.
+static MIN_POINTS: uint = 5;
+
+/// The baseline for each point of `xs`, computed from (up to)
+/// `WINDOW` points immediately before it, so a point is never
+/// compared against itself.
+pub fn rolling(xs: &[f64]) -> Vec<Option<Band>> {
+    range(0, xs.len()).map(|i| {
+        let lo = if i > WINDOW { i - WINDOW } else { 0 };
+        if i - lo < MIN_POINTS { None } else { Some(band(xs.slice(lo, i))) }
+    }).collect()
+}
+
+/// The median and MAD of `xs`.
+pub fn band(xs: &[f64]) -> Band {
+    let m = median(xs);
+    let deviations: Vec<f64> = xs.iter().map(|x| (x - m).abs()).collect();
+    Band { median: m, mad: median(deviations.as_slice()) }
+}
+
+/// A robust estimate of the standard deviation of the noise in `xs`,
+/// from the median absolute difference between neighbours (which
+/// ignores the level shifts themselves).
+pub fn sigma(xs: &[f64]) -> f64 {
+    let diffs: Vec<f64> = xs.windows(2).map(|w| (w[1] - w[0]).abs()).collect();
+    // the 1.4826 makes a MAD a standard deviation for normal data,
+    // and differencing multiplies the variance by 2.
+    1.4826 * median(diffs.as_slice()) / SQRT2
+}
+
+/// The median of `xs`, or NaN if it's empty.
+pub fn median(xs: &[f64]) -> f64 {
+    if xs.is_empty() { return f64::NAN }
+    let mut v = xs.to_vec();
+    v.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
+    let mid = v.len() / 2;
+    if v.len() % 2 == 0 { (v[mid - 1] + v[mid]) / 2.0 } else { v[mid] }
+}
@@ -0,0 +1,101 @@
+//! Putting the summary in the order the commits landed on master.
+//!
+//! Timestamps aren't good enough for this: bors can merge several
+//! things within a second, and author clocks can be skewed, so we
+//! walk the first-parent chain instead, and only fall back to
+//! timestamps for commits we can't place on it.
+
+use std::collections::{HashMap, HashSet};
+use Summary;
+
+/// Sort `summary` along the first-parent chain of master and number
+/// the entries consecutively in `sequence`.
+///
+/// First parents come from each entry's `parent` if it has one, and
+/// `extra_parents` otherwise (e.g. from a local git repository,
+/// which can also fill in commits that were never benchmarked). An
+/// entry without a `parent` gets its one from `extra_parents`.
+///
+/// `head` is master's newest commit, if we know it (from a
+/// repository); otherwise the tip is guessed from `summary`.
+pub fn order(mut summary: Vec<Summary>, head: Option<String>,
+             extra_parents: &HashMap<String, String>) -> Vec<Summary> {
+    let mut parents = extra_parents.clone();
+    for s in summary.iter_mut() {
+        if s.parent.is_none() {
+            s.parent = extra_parents.find(&s.hash).map(|p| p.clone());
+        }
+        match s.parent {
+            Some(ref p) => { parents.insert(s.hash.clone(), p.clone()); }
+            None => {}
+        }
+    }
+
+    // without a repository, master is the newest commit that isn't
+    // the first parent of anything we know about. (With one, every
+    // benchmarked commit but the clone's head is someone's first
+    // parent, and the clone is usually ahead of the benchmarks.)
+    let tip = head.or_else(|| {
+        let first_parents: HashSet<String> = parents.values().map(|p| p.clone()).collect();
+        summary.iter()
+            .filter(|s| !first_parents.contains(&s.hash))
+            .max_by(|s| (s.timestamp, s.hash.clone()))
+            .map(|s| s.hash.clone())
+    });
+
+    // where each hash is on the chain, counting back from the tip.
+    let mut depth = HashMap::new();
+    let mut current = tip;
+    loop {
+        let hash = match current {
+            Some(h) => h,
+            None => break
+        };
+        if depth.contains_key(&hash) { break } // a cycle?!
+        let d = depth.len();
+        current = parents.find(&hash).map(|p| p.clone());
+        depth.insert(hash, d);
+    }
+
+    let (mut chain, mut rest) = summary.partition(|s| depth.contains_key(&s.hash));
+    chain.sort_by(|a, b| depth.find(&b.hash).cmp(&depth.find(&a.hash)));
+    rest.sort_by(|a, b| (a.timestamp, &a.hash).cmp(&(b.timestamp, &b.hash)));
+
+    // slot the things that aren't on the chain in by timestamp,
+    // without disturbing the chain's order.
+    let mut ordered = Vec::with_capacity(chain.len() + rest.len());
+    {
+        let mut chain = chain.into_iter().peekable();
+        let mut rest = rest.into_iter().peekable();
+        loop {
+            let take_rest = match (chain.peek(), rest.peek()) {
+                (None, None) => break,
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (Some(c), Some(r)) => r.timestamp < c.timestamp
+            };
+            ordered.push(if take_rest { rest.next() } else { chain.next() }.unwrap());
+        }
+    }
+
+    for (i, s) in ordered.iter_mut().enumerate() {
+        s.sequence = Some(i);
+    }
+    ordered
+}
+
+/// The index of the entry to compare each entry of `summary` (which
+/// should be in order) against: its first parent if that was
+/// processed, otherwise the one before it. Most entries don't know
+/// their parent (the old commit_info.txt doesn't say), but the one
+/// before is the parent, or the nearest processed ancestor, whenever
+/// it's on the chain.
+pub fn parents(summary: &[Summary]) -> Vec<Option<uint>> {
+    let index: HashMap<&str, uint> = summary.iter().enumerate()
+        .map(|(i, s)| (s.hash.as_slice(), i))
+        .collect();
+    summary.iter().enumerate().map(|(i, s)| {
+        let recorded = s.parent.as_ref().and_then(|p| index.find(&p.as_slice()).map(|&j| j));
+        recorded.or(if i > 0 { Some(i - 1) } else { None })
+    }).collect()
+}
@@ -0,0 +1,164 @@
+//! A static page for each processed commit and each PR, so there's
+//! something to link to. They're Jekyll pages using
+//! _layouts/default.html, written to commit/<hash>.html and
+//! pr/<number>.html.
+
+use std::io::{mod, fs, File};
+use std::io::fs::PathExtensions;
+use serialize::Decodable;
+use serialize::json::{mod, Json};
+use diff::{mod, Diff};
+use pass_memory::PassMemory;
+use pull_requests;
+use svg::escape;
+use {Summary, MIB};
+
+/// Where the site is published.
+pub static SITE: &'static str = "http://huonw.github.io/isrustfastyet";
+static COMMIT_URL: &'static str = "https://github.com/rust-lang/rust/commit/";
+static PR_URL: &'static str = "https://github.com/rust-lang/rust/pull/";
+/// The number of passes listed in the change from the parent.
+static TOP_PASSES: uint = 10;
+
+/// Make the directories the pages go in.
+pub fn create_dirs() {
+    for dir in ["commit", "pr"].iter() {
+        fs::mkdir_recursive(&Path::new(*dir), io::USER_RWX)
+            .ok().expect(format!("can't create {}", dir).as_slice());
+    }
+}
+
+/// Write the pages of the commit `s`, with output `out`, and of its
+/// PRs. `parent` is the diff from the commit before it (see
+/// `order::parents`), if there is one.
+pub fn write(s: &Summary, out: &Json, parent: Option<&Diff>) {
+    let body = body(s, out, parent);
+
+    let hash8 = s.hash.as_slice().slice_to(8);
+    write_page(&Path::new("commit").join(s.hash.clone() + ".html"),
+               format!("Commit {}", hash8).as_slice(), body.as_slice());
+
+    let merge = s.pull_request;
+    let landed = s.pull_requests.clone().unwrap_or_else(|| merge.into_iter().collect());
+    for &pr in merge.iter().chain(landed.iter().filter(|&&pr| Some(pr) != merge)) {
+        let title = pull_requests::title(pr);
+        let heading = match title {
+            Some(ref t) => format!("#{}: {}", pr, t),
+            None => format!("#{}", pr)
+        };
+        let mut page = format!("<h1><a href=\"{}{}\">{}</a></h1>\n",
+                               PR_URL, pr, escape(heading.as_slice()));
+        match merge {
+            Some(m) if m != pr => {
+                page.push_str(format!("<p>Landed in rollup <a href=\"{}.html\">#{}</a>.</p>\n",
+                                      m, m).as_slice());
+            }
+            _ => {}
+        }
+        page.push_str(body.as_slice());
+        write_page(&Path::new("pr").join(format!("{}.html", pr)), heading.as_slice(), page.as_slice());
+    }
+}
+
+/// Write a page, with the front matter for the site's layout.
+fn write_page(path: &Path, title: &str, body: &str) {
+    let mut f = File::create(path)
+        .ok().expect(format!("can't write to {}", path.display()).as_slice());
+    // the raw stops a PR title with {{ in it being taken as Liquid.
+    f.write_str(format!("---\nlayout: default\ntitle: \"{}\"\nstyles: [../style.css]\n---\n\
+                         {{% raw %}}\n{}{{% endraw %}}\n",
+                        title.replace("\\", "\\\\").replace("\"", "\\\""), body).as_slice())
+        .unwrap();
+}
+
+/// Everything about the commit `s`, and how it differs from its parent.
+fn body(s: &Summary, out: &Json, parent: Option<&Diff>) -> String {
+    let hash = s.hash.as_slice();
+    let mut ret = format!("<h2>Commit <a href=\"{0}{1}\">{2}</a></h2>\n\
+                           <p><a href=\"../#{1}\">Interactive view</a></p>\n",
+                          COMMIT_URL, hash, hash.slice_to(8));
+    match s.pull_requests {
+        Some(ref prs) if prs.len() > 1 => {
+            let links: Vec<String> = prs.iter()
+                .map(|pr| format!("<a href=\"../pr/{0}.html\">#{0}</a>", pr))
+                .collect();
+            ret.push_str(format!("<p class=\"rollup-text\">Rolled up: {}</p>\n",
+                                 links.connect(", ")).as_slice());
+        }
+        _ => {}
+    }
+    // only commits processed since the SVGs were added have one.
+    if Path::new("out/svg").join(s.hash.clone() + ".svg").exists() {
+        ret.push_str(format!("<img src=\"../out/svg/{}.svg\" alt=\"Memory profile\">\n",
+                             hash).as_slice());
+    }
+
+    ret.push_str("<h3>Summary</h3>\n<table>\n");
+    ret.push_str(row("Max memory", mib(s.max_memory).as_slice()).as_slice());
+    match s.runs {
+        Some(ref r) => {
+            ret.push_str(row("Runs", format!("{} (max memory {} to {})", r.runs,
+                                             mib(r.max_memory.min), mib(r.max_memory.max))
+                             .as_slice()).as_slice());
+        }
+        None => {}
+    }
+    for &(label, x) in [("CPU time", s.cpu_time), ("Elapsed", s.elapsed)].iter() {
+        match x {
+            Some(x) => ret.push_str(row(label, secs(x).as_slice()).as_slice()),
+            None => {}
+        }
+    }
+    match s.memory_stats {
+        Some(ref m) => {
+            ret.push_str(row("Mean memory", mib(m.mean_memory).as_slice()).as_slice());
+            ret.push_str(row("Memory integral",
+                             format!("{:.1f} GB·s", m.memory_integral).as_slice()).as_slice());
+            ret.push_str(row("90th percentile", mib(m.memory_p90).as_slice()).as_slice());
+        }
+        None => {}
+    }
+    ret.push_str("</table>\n");
+
+    match parent {
+        Some(d) => {
+            ret.push_str(format!("<h3>Change from <a href=\"../commit/{0}.html\">{1}</a></h3>\n<table>\n",
+                                 d.a, d.a.as_slice().slice_to(8)).as_slice());
+            for delta in d.summary.iter().chain(d.passes.iter().take(TOP_PASSES)) {
+                let (a, b, change) = if delta.name.as_slice() == "max_memory" {
+                    (mib(delta.a), mib(delta.b), format!("{:+.1f} MiB", delta.delta / MIB))
+                } else {
+                    (secs(delta.a), secs(delta.b), format!("{:+.3f} s", delta.delta))
+                };
+                ret.push_str(format!("<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                                      <td>{}</td></tr>\n",
+                                     escape(delta.name.as_slice()), a, b, change,
+                                     diff::percent(delta.relative, delta.b)).as_slice());
+            }
+            ret.push_str("</table>\n");
+        }
+        None => {}
+    }
+
+    let passes: Vec<PassMemory> = out.find(&"pass_memory".into_string())
+        .and_then(|p| Decodable::decode(&mut json::Decoder::new(p.clone())).ok())
+        .unwrap_or_else(|| vec![]);
+    if !passes.is_empty() {
+        ret.push_str("<h3>Passes</h3>\n<table>\n\
+                      <tr><th>Pass</th><th>Start</th><th>Time</th><th>Peak</th><th>Growth</th></tr>\n");
+        for p in passes.iter() {
+            ret.push_str(format!("<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:+.1f} MiB</td></tr>\n",
+                                 escape(p.name.as_slice()), secs(p.start), secs(p.end - p.start),
+                                 mib(p.peak_memory), p.growth / MIB).as_slice());
+        }
+        ret.push_str("</table>\n");
+    }
+    ret
+}
+
+fn row(label: &str, value: &str) -> String {
+    format!("<tr><th>{}</th><td>{}</td></tr>\n", label, value)
+}
+
+fn mib(x: f64) -> String { format!("{:.1f} MiB", x / MIB) }
+fn secs(x: f64) -> String { format!("{:.3f} s", x) }
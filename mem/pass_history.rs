@@ -0,0 +1,68 @@
+//! Following each compiler pass's timing across commits.
+
+use std::collections::TreeMap;
+use serialize::json::Json;
+use regressions::{mod, Regression};
+use Summary;
+
+/// The pass timings recorded in an out/<hash>.json, in the order
+/// they ran.
+pub fn pass_list(out: &Json) -> Vec<(String, f64)> {
+    let passes = match out.find(&"pass_timing".into_string()).and_then(|p| p.as_list()) {
+        Some(p) => p,
+        None => return vec![]
+    };
+    passes.iter().filter_map(|pass| {
+        match pass.as_list() {
+            Some(p) if p.len() == 2 => {
+                match (p[0].as_string(), p[1].as_number()) {
+                    (Some(name), Some(t)) => Some((name.into_string(), t)),
+                    _ => None
+                }
+            }
+            _ => None
+        }
+    }).collect()
+}
+
+/// The pass timings recorded in an out/<hash>.json, with repeated
+/// passes added together and the subsection markers (the tiny
+/// negative times) dropped.
+pub fn pass_timing(out: &Json) -> TreeMap<String, f64> {
+    let mut ret = TreeMap::new();
+    for (name, t) in pass_list(out).into_iter() {
+        if t < 0.0 { continue }
+        let total = ret.find(&name).map(|x| *x).unwrap_or(0.0);
+        ret.insert(name, total + t);
+    }
+    ret
+}
+
+/// Collect the time of each pass at each commit of `summary` (which
+/// should be in order), using `load` to look up out/<hash>.json.
+pub fn series<'a, 'b>(summary: &'a [Summary], load: |&str| -> Option<&'b Json>)
+                      -> TreeMap<String, Vec<(&'a Summary, f64)>> {
+    let mut ret = TreeMap::new();
+    for s in summary.iter() {
+        let out = match load(s.hash.as_slice()) {
+            Some(o) => o,
+            None => continue
+        };
+        for (name, t) in pass_timing(out).into_iter() {
+            if !ret.contains_key(&name) {
+                ret.insert(name.clone(), vec![]);
+            }
+            ret.find_mut(&name).unwrap().push((s, t));
+        }
+    }
+    ret
+}
+
+/// Run change-point detection over every pass's series.
+pub fn detect(series: &TreeMap<String, Vec<(&Summary, f64)>>) -> Vec<Regression> {
+    let mut ret = vec![];
+    for (name, points) in series.iter() {
+        ret.extend(regressions::detect_series(name.as_slice(), points.as_slice()).into_iter());
+    }
+    ret
+}
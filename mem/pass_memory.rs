@@ -0,0 +1,68 @@
+//! Lining the passes up with the memory profile.
+//!
+//! The passes are run one after the other, so laying their durations
+//! end to end from the start of the trace says roughly when each one
+//! ran. It's only roughly because the time before the first pass
+//! (e.g. loading the binary) isn't recorded, but it's close enough to
+//! say which pass a spike belongs to.
+
+use resample::linear;
+
+/// What the memory did during one pass.
+#[deriving(Encodable, Decodable, Clone, Show)]
+pub struct PassMemory {
+    pub name: String,
+    /// seconds since the start of the trace
+    pub start: f64,
+    pub end: f64,
+    /// bytes
+    pub start_memory: f64,
+    pub end_memory: f64,
+    pub peak_memory: f64,
+    /// end_memory - start_memory
+    pub growth: f64,
+}
+
+/// Place each of `passes` on `memory`, using the raw (not simplified)
+/// trace so that short spikes aren't lost.
+pub fn attribute(memory: &[(f64, f64)], passes: &[(String, f64)]) -> Vec<PassMemory> {
+    if memory.is_empty() { return vec![] }
+    let (mut time, _) = memory[0];
+
+    passes.iter().filter_map(|&(ref name, duration)| {
+        // the subsection markers (negative) don't take any time of
+        // their own; their subpasses have already been placed.
+        if duration < 0.0 { return None }
+
+        let (start, end) = (time, time + duration);
+        time = end;
+
+        let start_memory = linear(memory, start);
+        let end_memory = linear(memory, end);
+        let peak_memory = memory.iter()
+            .filter(|&&(t, _)| start <= t && t <= end)
+            .fold(start_memory.max(end_memory), |a, &(_, m)| a.max(m));
+
+        Some(PassMemory {
+            name: name.clone(),
+            start: start,
+            end: end,
+            start_memory: start_memory,
+            end_memory: end_memory,
+            peak_memory: peak_memory,
+            growth: end_memory - start_memory,
+        })
+    }).collect()
+}
+
+/// The pass during which memory usage was highest.
+pub fn peak_pass(passes: &[PassMemory]) -> Option<String> {
+    let mut best: Option<&PassMemory> = None;
+    for p in passes.iter() {
+        match best {
+            Some(b) if b.peak_memory >= p.peak_memory => {}
+            _ => best = Some(p)
+        }
+    }
+    best.map(|p| p.name.clone())
+}
@@ -0,0 +1,146 @@
+//! How each PR moved the numbers, written next to its metadata as
+//! pull_requests/NN/NNNN/perf_impact.json.
+
+use std::collections::{HashMap, TreeMap};
+use std::io::File;
+use std::io::fs::PathExtensions;
+use serialize::json;
+use diff::{Delta, Diff};
+use noise::{mod, Band};
+use pull_requests;
+use Summary;
+
+/// A merge counts as significant if it ends up this many MADs from
+/// the baseline, and its own change is at least this many MADs.
+pub static SIGNIFICANT: f64 = 3.0;
+
+/// The change in one number from the parent to the merge commit.
+#[deriving(Encodable, Decodable, Clone)]
+pub struct Impact {
+    pub name: String,
+    pub before: f64,
+    pub after: f64,
+    /// after - before
+    pub delta: f64,
+    /// (after - before) / before, or None if before is 0.
+    pub relative: Option<f64>,
+    /// The level of the merges before this one, if there's enough
+    /// history (and variation) to say.
+    pub baseline: Option<Band>,
+    /// How many MADs `after` is from the baseline.
+    pub mads: Option<f64>,
+    pub significant: bool,
+}
+
+#[deriving(Encodable, Decodable, Clone)]
+pub struct PerfImpact {
+    pub pull_request: uint,
+    /// The rollup it landed in, if it wasn't merged by itself, in
+    /// which case the numbers cover everything rolled up with it.
+    pub rollup: Option<uint>,
+    pub hash: String,
+    /// The commit compared against.
+    pub parent: String,
+    /// max_memory, cpu_time and elapsed.
+    pub summary: Vec<Impact>,
+    /// Every pass, biggest change first.
+    pub passes: Vec<Impact>,
+}
+
+/// The rolling baseline of each metric and pass at each commit of
+/// `summary` (which should be in order), by hash. Each only uses the
+/// commits before it, so an impact doesn't change once it's written.
+pub fn baselines(summary: &[Summary], passes: &TreeMap<String, Vec<(&Summary, f64)>>)
+                 -> HashMap<String, TreeMap<String, Band>> {
+    let mut ret: HashMap<String, TreeMap<String, Band>> = noise::baselines(summary).into_iter()
+        .map(|b| (b.hash, b.bands))
+        .collect();
+    for (name, points) in passes.iter() {
+        let xs: Vec<f64> = points.iter().map(|&(_, x)| x).collect();
+        for (&(s, _), band) in points.iter().zip(noise::rolling(xs.as_slice()).into_iter()) {
+            match band {
+                Some(b) => {
+                    ret.find_or_insert_with(s.hash.clone(), |_| TreeMap::new())
+                        .insert(name.clone(), b);
+                }
+                None => {}
+            }
+        }
+    }
+    ret
+}
+
+/// Every PR that landed in `s`: the one that merged it, the ones it
+/// rolled up, and `metadata`, the PRs whose merge_commit.txt names
+/// it.
+pub fn landed(s: &Summary, metadata: &[uint]) -> Vec<uint> {
+    let mut ret: Vec<uint> = s.pull_request.into_iter().collect();
+    let rolled_up = s.pull_requests.as_ref().map(|prs| prs.as_slice()).unwrap_or(&[]);
+    for &pr in rolled_up.iter().chain(metadata.iter()) {
+        if !ret.contains(&pr) {
+            ret.push(pr);
+        }
+    }
+    ret
+}
+
+/// The impact of the PR `pr`, which merged as `d.b` on top of `d.a`
+/// (maybe as part of the rollup `rollup`), where `bands` is the
+/// baseline at `d.b`.
+pub fn impact(pr: uint, rollup: Option<uint>, d: &Diff, bands: &TreeMap<String, Band>)
+              -> PerfImpact {
+    PerfImpact {
+        pull_request: pr,
+        rollup: rollup,
+        hash: d.b.clone(),
+        parent: d.a.clone(),
+        summary: impacts(d.summary.as_slice(), bands),
+        passes: impacts(d.passes.as_slice(), bands),
+    }
+}
+
+/// Compare each of `deltas` with the baseline of that metric or pass.
+pub fn impacts(deltas: &[Delta], bands: &TreeMap<String, Band>) -> Vec<Impact> {
+    deltas.iter().map(|x| convert(x, bands)).collect()
+}
+
+fn convert(delta: &Delta, bands: &TreeMap<String, Band>) -> Impact {
+    // with no variation at all, any change would be infinitely many
+    // MADs out, which says more about the rounding than the PR.
+    let baseline = match bands.find(&delta.name) {
+        Some(b) if b.mad > 0.0 => Some(b.clone()),
+        _ => None
+    };
+    let mads = baseline.as_ref().map(|b| b.mads(delta.b));
+    // after a real step, the baseline takes a while to catch up, so
+    // landing away from it isn't enough: the PR itself has to have
+    // moved the number, and in the same direction.
+    let significant = match (&baseline, mads) {
+        (&Some(ref b), Some(m)) => {
+            m.abs() >= SIGNIFICANT && delta.delta.abs() >= SIGNIFICANT * b.mad
+                && (m > 0.0) == (delta.delta > 0.0)
+        }
+        _ => false
+    };
+    Impact {
+        name: delta.name.clone(),
+        before: delta.a,
+        after: delta.b,
+        delta: delta.delta,
+        relative: delta.relative,
+        baseline: baseline,
+        mads: mads,
+        significant: significant,
+    }
+}
+
+/// Write `impact` into its PR's directory, if the PR scripts have
+/// made one.
+pub fn write(impact: &PerfImpact) {
+    let dir = pull_requests::dir(impact.pull_request);
+    if !dir.is_dir() { return }
+    let path = dir.join("perf_impact.json");
+    let mut f = File::create(&path)
+        .ok().expect(format!("can't write to {}", path.display()).as_slice());
+    f.write_str(json::encode(impact).as_slice()).unwrap();
+}
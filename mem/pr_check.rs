@@ -0,0 +1,144 @@
+//! Checking the PR parsed from each commit's message against GitHub's
+//! idea of the merge commit, from pull_requests/*/merge_commit.txt.
+//! They disagree now and then, e.g. when bors retried a PR or
+//! something was rebased.
+
+use std::collections::{HashMap, HashSet, TreeMap};
+use std::io::fs::PathExtensions;
+use pull_requests;
+use Summary;
+
+/// A processed commit that the message and the metadata disagree on.
+#[deriving(Encodable)]
+pub struct Mismatch {
+    pub hash: String,
+    /// The PR from the commit message.
+    pub parsed: Option<uint>,
+    /// The PR whose merge_commit.txt names this commit.
+    pub metadata: Option<uint>,
+}
+
+#[deriving(Encodable)]
+pub struct Report {
+    /// (PR, merge commit) for the PRs whose merge commit was never
+    /// processed.
+    pub unprocessed: Vec<(uint, String)>,
+    /// (commit, PR) for the processed commits whose PR has no
+    /// pull_requests directory.
+    pub no_directory: Vec<(String, uint)>,
+    /// (commit, PR) for the processed commits whose PR has a
+    /// directory, but no merge commit recorded in it.
+    pub no_merge_commit: Vec<(String, uint)>,
+    /// (commit, PRs) for the commits that several merge_commit.txts
+    /// name, which can't be checked (or fixed).
+    pub ambiguous: Vec<(String, Vec<uint>)>,
+    pub mismatched: Vec<Mismatch>,
+}
+
+/// Compare `summary` with `merges`, the merge commit of each PR.
+pub fn check(summary: &[Summary], merges: &TreeMap<uint, String>) -> Report {
+    let mut by_hash: HashMap<&str, Vec<uint>> = HashMap::new();
+    for (&pr, h) in merges.iter() {
+        by_hash.find_or_insert_with(h.as_slice(), |_| vec![]).push(pr);
+    }
+    let processed: HashSet<&str> = summary.iter().map(|s| s.hash.as_slice()).collect();
+
+    // the data only goes back so far, so older PRs are expected to
+    // be missing.
+    let unprocessed = match summary.iter().filter_map(|s| s.pull_request).min() {
+        Some(oldest) => {
+            merges.iter()
+                .filter(|&(&pr, h)| pr >= oldest && !processed.contains(&h.as_slice()))
+                .map(|(&pr, h)| (pr, h.clone()))
+                .collect()
+        }
+        None => vec![]
+    };
+
+    let mut no_directory = vec![];
+    let mut no_merge_commit = vec![];
+    let mut ambiguous = vec![];
+    let mut mismatched = vec![];
+    for s in summary.iter() {
+        let known = match s.pull_request {
+            Some(pr) if !merges.contains_key(&pr) => {
+                if pull_requests::dir(pr).is_dir() {
+                    no_merge_commit.push((s.hash.clone(), pr));
+                } else {
+                    no_directory.push((s.hash.clone(), pr));
+                }
+                false
+            }
+            _ => true
+        };
+        let metadata = match by_hash.find(&s.hash.as_slice()) {
+            Some(prs) if prs.len() > 1 => {
+                ambiguous.push((s.hash.clone(), prs.clone()));
+                continue
+            }
+            Some(prs) => Some(prs[0]),
+            None => None
+        };
+        // a PR without metadata has already been reported, and can't
+        // disagree with anything else.
+        if metadata != s.pull_request && (known || metadata.is_some()) {
+            mismatched.push(Mismatch {
+                hash: s.hash.clone(),
+                parsed: s.pull_request,
+                metadata: metadata,
+            });
+        }
+    }
+
+    Report {
+        unprocessed: unprocessed,
+        no_directory: no_directory,
+        no_merge_commit: no_merge_commit,
+        ambiguous: ambiguous,
+        mismatched: mismatched,
+    }
+}
+
+/// Take the PR from the metadata for every mismatch that has one,
+/// returning how many commits changed.
+pub fn fix(summary: &mut [Summary], report: &Report) -> uint {
+    let mut fixed = 0;
+    for m in report.mismatched.iter() {
+        let pr = match m.metadata {
+            Some(pr) => pr,
+            None => continue
+        };
+        for s in summary.iter_mut().filter(|s| s.hash == m.hash) {
+            // the constituents only need changing if this wasn't a
+            // rollup, i.e. they just repeated the old PR.
+            if s.pull_requests == Some(s.pull_request.into_iter().collect()) {
+                s.pull_requests = Some(vec![pr]);
+            }
+            s.pull_request = Some(pr);
+            fixed += 1;
+        }
+    }
+    fixed
+}
+
+/// Write `r` out for a person to read.
+pub fn print_text(r: &Report) {
+    for &(pr, ref hash) in r.unprocessed.iter() {
+        println!("#{} merged as {}, which hasn't been processed", pr, hash);
+    }
+    for &(ref hash, pr) in r.no_directory.iter() {
+        println!("{} is #{}, which has no pull_requests directory", hash, pr);
+    }
+    for &(ref hash, pr) in r.no_merge_commit.iter() {
+        println!("{} is #{}, which has no merge commit recorded", hash, pr);
+    }
+    for &(ref hash, ref prs) in r.ambiguous.iter() {
+        let prs: Vec<String> = prs.iter().map(|pr| format!("#{}", pr)).collect();
+        println!("{} is the merge commit of all of {}, so wasn't checked", hash, prs.connect(", "));
+    }
+    for m in r.mismatched.iter() {
+        let show = |pr: Option<uint>| pr.map(|pr| format!("#{}", pr)).unwrap_or("no PR".into_string());
+        println!("{} is {} by its message, but {} by merge_commit.txt",
+                 m.hash, show(m.parsed), show(m.metadata));
+    }
+}
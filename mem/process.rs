@@ -2,13 +2,40 @@
 
 extern crate serialize;
 extern crate collections;
+extern crate time;
 use serialize::{json, Decodable, Encodable};
-use std::task;
-use std::io::{mod, fs, File};
+use std::{os, task};
+use std::io::{mod, fs, File, IoError};
 use std::io::fs::PathExtensions;
-use std::collections::HashSet;
-
+use std::collections::{HashMap, HashSet, TreeMap};
+
+mod align;
+mod changepoint;
+mod comment;
+mod commit_info;
+mod diff;
+mod feed;
+mod git;
+mod growth;
+mod heatmap;
 mod line_simplify;
+mod mem_stats;
+mod noise;
+mod order;
+mod pages;
+mod perf_impact;
+mod pass_history;
+mod pass_memory;
+mod pr_check;
+mod pull_requests;
+mod regressions;
+mod resample;
+mod runs;
+mod similar;
+mod spikes;
+mod svg;
+mod time_parse;
+mod validate;
 
 trait Expect<T> { fn expect(self, String) -> T; }
 impl<T, U> Expect<T> for Result<T, U> {
@@ -47,17 +74,162 @@ struct Data {
 #[deriving(Encodable)]
 struct Output {
     summary: Summary,
+    commit: commit_info::CommitInfo,
     memory_data: Vec<(f64, f64)>,
     pass_timing: Vec<(String, f64)>,
+    pass_memory: Vec<pass_memory::PassMemory>,
+    // the pass during which the memory peaked.
+    peak_pass: Option<String>,
+    quality: validate::Quality,
+    // the sampler glitches found, if we looked for them.
+    spikes: Option<spikes::Spikes>,
+    growth: growth::Growth,
+    // the median and range of the memory curves, if there were
+    // several runs.
+    run_memory: Option<runs::RunCurves>,
 }
 
-#[deriving(Encodable, Decodable, Clone, PartialOrd, PartialEq)]
+#[deriving(Encodable, Decodable, Clone, PartialEq)]
 struct Summary {
     timestamp: uint,
     hash: String,
     max_memory: f64,
     cpu_time: Option<f64>,
-    pull_request: Option<uint>
+    elapsed: Option<f64>,
+    // averages, integrals and percentiles of the unsimplified memory
+    // trace (less any spikes removed).
+    memory_stats: Option<mem_stats::MemoryStats>,
+    pull_request: Option<uint>,
+    // the PRs that actually landed, which differ from `pull_request`
+    // for a rollup. None for commits processed before this existed.
+    pull_requests: Option<Vec<uint>>,
+    // the first parent, and the position along master's first-parent
+    // chain (i.e. the index into summary.json).
+    parent: Option<String>,
+    sequence: Option<uint>,
+    // how much the runs varied, if there were several: max_memory
+    // and cpu_time are then the medians.
+    runs: Option<runs::RunStats>,
+}
+
+/// The names of the numbers in `Summary` that we track over time.
+static METRICS: [&'static str, .. 2] = ["max_memory", "cpu_time"];
+
+impl Summary {
+    /// Look up one of `METRICS`.
+    fn metric(&self, name: &str) -> Option<f64> {
+        match name {
+            "max_memory" => Some(self.max_memory),
+            "cpu_time" => self.cpu_time,
+            _ => fail!("unknown metric {}", name)
+        }
+    }
+}
+
+/// Bytes in a MiB, the unit memory is shown in.
+static MIB: f64 = 1024.0 * 1024.0;
+
+/// `x`, a value of `name` (one of `METRICS`, or a pass), with its unit.
+fn format_value(name: &str, x: f64) -> String {
+    if name == "max_memory" { format!("{:.1f} MiB", x / MIB) } else { format!("{:.2f} s", x) }
+}
+
+/// What we've been asked to do.
+enum Mode {
+    /// Process any new data, and update the summary.
+    Process,
+    /// Compare two already-processed commits.
+    Diff(String, String),
+    /// Find the commits with profiles most like this one.
+    Similar(String),
+    /// Group the history into this many eras of similar profiles.
+    Cluster(uint),
+    /// Compare the PR of each commit with the PR metadata.
+    CheckPrs,
+    /// Write a Markdown report of a PR's (or a commit's) impact.
+    Comment(String),
+}
+
+/// Command line options.
+struct Config {
+    mode: Mode,
+    /// A local clone of rust-lang/rust.
+    git: Option<Path>,
+    /// Print JSON rather than text, for the modes that print.
+    json: bool,
+    /// What to do about single-sample spikes in the memory traces.
+    spikes: spikes::Action,
+    /// The time axis of heatmap.json.
+    heatmap: resample::Grid,
+    /// How many commits `similar` lists.
+    k: uint,
+    /// Whether `check-prs` should correct the summary.
+    fix: bool,
+}
+
+fn parse_args() -> Config {
+    let args = os::args();
+    let mut config = Config {
+        mode: Process,
+        git: None,
+        json: false,
+        spikes: spikes::Ignore,
+        heatmap: resample::Percent(100),
+        k: 10,
+        fix: false,
+    };
+    let mut args = args.iter().skip(1);
+    loop {
+        match args.next().map(|a| a.as_slice()) {
+            None => break,
+            Some("--git") => {
+                let path = args.next().expect("--git needs a path");
+                config.git = Some(Path::new(path.as_slice()));
+            }
+            Some("--json") => config.json = true,
+            Some("--spikes") => {
+                config.spikes = match args.next().map(|a| a.as_slice()) {
+                    Some("flag") => spikes::Flag,
+                    Some("remove") => spikes::Remove,
+                    _ => fail!("--spikes needs `flag` or `remove`")
+                }
+            }
+            Some("--heatmap-seconds") => {
+                // a step of zero or less would never get anywhere.
+                let step = args.next().and_then(|a| from_str::<f64>(a.as_slice()))
+                    .and_then(|s| if s > 0.0 { Some(s) } else { None })
+                    .expect("--heatmap-seconds needs a positive number of seconds");
+                config.heatmap = resample::Seconds(step);
+            }
+            Some("--fix") => config.fix = true,
+            Some("-k") => {
+                config.k = args.next().and_then(|a| from_str(a.as_slice()))
+                    .expect("-k needs a number");
+            }
+            Some("similar") => {
+                let hash = args.next().expect("usage: process similar <hash>");
+                config.mode = Similar(hash.clone());
+            }
+            Some("cluster") => {
+                let k = args.next().and_then(|a| from_str(a.as_slice()))
+                    .expect("usage: process cluster <number of clusters>");
+                config.mode = Cluster(k);
+            }
+            Some("check-prs") => config.mode = CheckPrs,
+            Some("comment") => {
+                let target = args.next().expect("usage: process comment <PR number or hash>");
+                config.mode = Comment(target.clone());
+            }
+            Some("diff") => {
+                match (args.next(), args.next()) {
+                    (Some(a), Some(b)) => config.mode = Diff(a.clone(), b.clone()),
+                    _ => fail!("usage: process diff <hashA> <hashB>")
+                }
+            }
+            Some(a) => fail!("unknown argument: {}", a)
+        }
+    }
+    config
 }
 
 // The list of all hashes that we know about.
@@ -81,6 +253,118 @@ fn load_summary(p: &Path) -> Vec<Summary> {
     }
 }
 
+/// One run of the benchmark on a commit.
+struct Run {
+    data: Data,
+    /// memory_data, less any removed spikes
+    memory: Vec<(f64, f64)>,
+    spikes: Option<spikes::Spikes>,
+    max_memory: f64,
+    cpu_time: Option<f64>,
+    quality: validate::Quality,
+}
+
+/// The directories holding each run for a commit, with a label for
+/// messages: data/data/<hash>/run-N/ if there are any, otherwise
+/// just data/data/<hash>/.
+fn run_dirs(hash_folder: &Path) -> Vec<(String, Path)> {
+    let hash = hash_folder.filename_str().unwrap_or("").into_string();
+    let mut runs: Vec<(uint, Path)> = fs::readdir(hash_folder).unwrap_or(vec![])
+        .into_iter()
+        .filter(|p| p.is_dir())
+        .filter_map(|p| {
+            let n = p.filename_str()
+                .and_then(|f| if f.starts_with("run-") { from_str(f.slice_from(4)) } else { None });
+            n.map(|n| (n, p.clone()))
+        })
+        .collect();
+    if runs.is_empty() {
+        return vec![(hash, hash_folder.clone())]
+    }
+    runs.sort_by(|&(a, _), &(b, _)| a.cmp(&b));
+    runs.into_iter().map(|(n, p)| (format!("{}/run-{}", hash, n), p)).collect()
+}
+
+/// Load the mem.json and time.txt in `dir`.
+fn load_run(dir: &Path, label: String, spike_action: spikes::Action) -> Run {
+    // time.txt existing => it should be valid, but one odd file
+    // shouldn't lose the rest of this commit's data, so just
+    // complain and carry on without it.
+    let cpu_time = File::open(&dir.join("time.txt")).ok().and_then(|mut file| {
+        let raw_time = match file.read_to_end() {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(s) => s,
+                Err(_) => {
+                    println!("{}: ignoring time.txt: it isn't UTF-8", label);
+                    return None
+                }
+            },
+            Err(e) => {
+                println!("{}: ignoring time.txt: {}", label, e);
+                return None
+            }
+        };
+        match time_parse::parse(raw_time.as_slice()) {
+            Ok(t) => Some(t.cpu_time()),
+            Err(e) => {
+                println!("{}: ignoring time.txt: {}", label, e);
+                None
+            }
+        }
+    });
+
+    // load the mem.json file.
+    let json = File::open(&dir.join("mem.json")).map(|mut rdr| {
+        json::from_reader(&mut rdr as &mut Reader)
+            .expect(format!("{}/mem.json is not json", label))
+    }).expect(format!("no {}/mem.json", label));
+
+    let d: Data = Decodable::decode(&mut json::Decoder::new(json)).unwrap();
+    let quality = validate::check(&d);
+    for problem in quality.problems.iter() {
+        println!("{}: {}", label, problem);
+    }
+    let (memory, spikes) = spikes::filter(d.memory_data.as_slice(), spike_action);
+    // if a removed spike was the peak, mem.json's max_memory is the
+    // spike; otherwise keep it, so the series has a single source.
+    let peak = memory.iter().fold(0.0, |a, &(_, m)| a.max(m));
+    let max_memory = match spikes {
+        Some(ref s) if s.removed && s.points.iter().any(|&(_, m)| m >= peak) => peak,
+        _ => d.max_memory as f64
+    };
+
+    Run {
+        data: d,
+        memory: memory,
+        spikes: spikes,
+        max_memory: max_memory,
+        cpu_time: cpu_time,
+        quality: quality,
+    }
+}
+
+/// Read the out/<hash>.json we wrote for a commit, if there is one.
+fn load_output(hash: &str) -> Option<json::Json> {
+    File::open(&Path::new("out").join(format!("{}.json", hash))).ok().and_then(|mut rdr| {
+        json::from_reader(&mut rdr as &mut Reader).ok()
+    })
+}
+
+/// Read the out/<hash>.json of every commit of `summary` that has
+/// one, so each is only parsed once.
+fn load_outputs<'a>(summary: &'a [Summary]) -> HashMap<&'a str, json::Json> {
+    summary.iter()
+        .filter_map(|s| load_output(s.hash.as_slice()).map(|out| (s.hash.as_slice(), out)))
+        .collect()
+}
+
+/// Rewrite the out/<hash>.json of a commit.
+fn write_output(hash: &str, out: &json::Json) {
+    let path = Path::new("out").join(format!("{}.json", hash));
+    let mut f = File::create(&path).expect(format!("can't write to {}", path.display()));
+    f.write_str(out.to_string().as_slice()).unwrap();
+}
+
 /// A list of commits we've already seen
 fn already_processed(summary: &[Summary]) -> Vec<String> {
     summary.iter().map(|x| x.hash.clone()).collect()
@@ -93,20 +377,6 @@ fn simplify_memory_data(v: &[(f64, f64)]) -> Vec<(f64, f64)> {
     line_simplify::visvalingam(v, 100000.0)
 }
 
-/// A parser for the output of GNU time
-fn extract_time(time_str: &str) -> (f64, f64) {
-    // we fail here because we assume that time.txt existing => it
-    // should be valid. Ignoring time.txt requires removing it.
-    let i = time_str.find_str("user ").expect("time is formatted wrong: missing user");
-    let j = time_str.find_str("system ").expect("time is formatted wrong: missing system");
-    // reading directly as f64 doesn't work on some computers! :(
-    let user = from_str::<f64>(time_str.slice_to(i))
-        .expect("time is formatted wrong: user not a f64") as f64;
-    let system = from_str::<f64>(time_str.slice(i + 5, j))
-        .expect("time is formatted wrong: system not a f64") as f64;
-    (user, system)
-}
-
 /// A parser for the time-passes output of rustc.
 pub fn pass_timing(s: &str) -> Vec<(String, f64)> {
     let mut last_indent = 0u;
@@ -143,8 +413,95 @@ pub fn pass_timing(s: &str) -> Vec<(String, f64)> {
 }
 
 fn main() {
+    let config = parse_args();
+    match config.mode {
+        Process => process(&config),
+        Diff(ref a, ref b) => {
+            let load = |hash: &String| {
+                load_output(hash.as_slice()).expect(format!("no out/{}.json", hash).as_slice())
+            };
+            let (a_out, b_out) = (load(a), load(b));
+            let d = diff::diff(a.as_slice(), &a_out, b.as_slice(), &b_out);
+            if config.json { diff::print_json(&d) } else { diff::print_text(&d) }
+        }
+        Similar(ref hash) => {
+            let summary = load_summary(&Path::new("out/summary.json"));
+            let profiles = similar::profiles(summary.as_slice(), |h| load_output(h));
+            let matches = similar::nearest(profiles.as_slice(), hash.as_slice(), config.k)
+                .expect(format!("{} hasn't been processed", hash).as_slice());
+            if config.json {
+                println!("{}", json::encode(&matches));
+            } else {
+                for m in matches.iter() {
+                    let pr = m.pull_request.map(|pr| format!("#{}", pr)).unwrap_or(String::new());
+                    println!("{} {:>7} {:.4f}", m.hash, pr, m.distance);
+                }
+            }
+        }
+        Cluster(k) => {
+            let summary = load_summary(&Path::new("out/summary.json"));
+            let profiles = similar::profiles(summary.as_slice(), |h| load_output(h));
+            let clusters = similar::cluster(profiles.as_slice(), k);
+            write_json_lines(&Path::new("out/clusters.json"), clusters.as_slice());
+        }
+        CheckPrs => {
+            let summary_path = Path::new("out/summary.json");
+            let mut summary = load_summary(&summary_path);
+            let report = pr_check::check(summary.as_slice(), &pull_requests::merge_commits());
+            if config.json { println!("{}", json::encode(&report)) } else { pr_check::print_text(&report) }
+            if config.fix {
+                let fixed = pr_check::fix(summary.as_mut_slice(), &report);
+                if !config.json { println!("corrected the PR of {} commits", fixed) }
+                write_json_lines(&summary_path, summary.as_slice());
+            }
+        }
+        Comment(ref target) => {
+            let summary = load_summary(&Path::new("out/summary.json"));
+            let s = comment::find(summary.as_slice(), target.as_slice())
+                .expect(format!("{} hasn't been processed", target).as_slice());
+            // the same commit the pages compare against.
+            let i = summary.iter().position(|x| x.hash == s.hash).unwrap();
+            let parent = order::parents(summary.as_slice())[i]
+                .map(|j| summary[j].hash.as_slice())
+                .expect(format!("{} is the first commit, so has nothing to compare with", s.hash)
+                        .as_slice());
+            // the baselines need every output anyway.
+            let outputs = load_outputs(summary.as_slice());
+            let out = outputs.find(&s.hash.as_slice())
+                .expect(format!("no out/{}.json", s.hash).as_slice());
+            let parent_out = outputs.find(&parent)
+                .expect(format!("no out/{}.json for the parent", parent).as_slice());
+            let d = diff::diff(parent, parent_out, s.hash.as_slice(), out);
+
+            let passes = pass_history::series(summary.as_slice(), |h| outputs.find(&h));
+            let mut bands = perf_impact::baselines(summary.as_slice(), &passes);
+            let bands = bands.pop(&s.hash).unwrap_or_else(|| TreeMap::new());
+            // a target that isn't a hash is a PR, maybe one of a rollup.
+            let pr = if s.hash.as_slice().starts_with(target.as_slice()) {
+                s.pull_request
+            } else {
+                from_str(target.as_slice())
+            };
+            let text = comment::render(s, pr, &d, &bands);
+
+            fs::mkdir_recursive(&Path::new("out/comments"), io::USER_RWX)
+                .expect("can't create out/comments".into_string());
+            let path = Path::new("out/comments").join(match pr {
+                Some(pr) => format!("{}.md", pr),
+                None => format!("{}.md", s.hash)
+            });
+            let mut f = File::create(&path).expect(format!("can't write to {}", path.display()));
+            f.write_str(text.as_slice()).unwrap();
+            println!("wrote {}", path.display());
+        }
+    }
+}
+
+fn process(config: &Config) {
     let summary_path = Path::new("out/summary.json");
     let mut summary = load_summary(&summary_path);
+    // to see what this run changed.
+    let old_summary = summary.clone();
 
     // work out what we're going to process
     let mut to_process = processing_possibilities();
@@ -152,6 +509,9 @@ fn main() {
         to_process.remove(&hash);
     }
 
+    fs::mkdir_recursive(&Path::new("out/svg"), io::USER_RWX)
+        .expect("can't create out/svg".into_string());
+
     // necessary in case the subtask fails
     let mut results = Vec::with_capacity(to_process.len());
 
@@ -161,6 +521,8 @@ fn main() {
         println!("{}", hash);
 
         let tsk = task::TaskBuilder::new();
+        let git_repo = config.git.clone();
+        let spike_action = config.spikes.clone();
 
         // parallelism!
         results.push((p, tsk.try_future(proc() {
@@ -170,69 +532,96 @@ fn main() {
             if !hash_folder.is_dir() {
                 println!("{} doesn't exist; skipping.", hash);
             } else {
-                let mem_path = hash_folder.join("mem.json");
-                let time_path = hash_folder.join("time.txt");
                 let ci_path = hash_folder.join("commit_info.txt");
 
-                let time_file = File::open(&time_path);
-                let time = time_file.map(|mut file| {
-                    let raw_time =
-                        String::from_utf8(file.read_to_end()
-                                           .expect("Couldn't read time.txt".into_string()));
-                    let (user, system) = extract_time(raw_time
-                                                      .expect("Non-utf8 time.txt".into_string())
-                                                      .as_slice());
-                    user + system
+                // prefer the clone, since it's authoritative, but it
+                // might be out of date.
+                let from_git = git_repo.as_ref().and_then(|repo| {
+                    let ci = git::commit_info(repo, hash.as_slice());
+                    if ci.is_none() {
+                        println!("{} isn't a commit in {}; using commit_info.txt",
+                                 hash, repo.display());
+                    }
+                    ci
                 });
-                let time = match time { Ok(o) => Some(o), Err(_) => None };
-
-                let mut ci_file = File::open(&ci_path)
-                    .expect(format!("no {}/commit_info.txt", hash));
-                let raw_commit_info =
-                    String::from_utf8(ci_file.read_to_end()
-                                      .expect("couldn't read commit_info.txt".into_string()))
+                let commit = from_git.unwrap_or_else(|| {
+                    let mut ci_file = File::open(&ci_path)
+                        .expect(format!("no {}/commit_info.txt", hash));
+                    let raw_commit_info =
+                        String::from_utf8(ci_file.read_to_end()
+                                          .expect("couldn't read commit_info.txt".into_string()))
                         .expect("Non-utf8 commit_info.txt".into_string());
-                let mut lines = raw_commit_info.as_slice().lines();
-                let (author, timestamp, summary) = match (lines.next(),
-                                                          lines.next().and_then(from_str),
-                                                          lines.next()) {
-                    (Some(a), Some(b), Some(c)) => (a, b, c),
-                    _ => fail!("invalid {}/commit_info.txt", hash)
-                };
+                    commit_info::parse(raw_commit_info.as_slice())
+                        .expect(format!("invalid {}/commit_info.txt", hash).as_slice())
+                });
 
-                let pull_request = if author == "bors bors@rust-lang.org" {
-                    let i = summary.find('#').expect("Bors merge without a number?") + 1;
-                    // a bors commit, so extract the pull request
-                    let leading_num = summary.slice_from(i);
-                    let non_num = leading_num.find(|c: char| !c.is_digit()).unwrap_or(0);
-                    from_str(leading_num.slice_to(non_num))
+                // the author isn't needed: the message formats are
+                // distinctive enough that we don't pick up stray #s.
+                let prs = commit_info::pull_requests(commit.message.as_slice());
+
+                let mut all_runs: Vec<Run> = run_dirs(&hash_folder).into_iter().map(|(label, dir)| {
+                    load_run(&dir, label, spike_action)
+                }).collect();
+
+                // summarise all the runs, but take everything else
+                // from a typical one.
+                let maxes: Vec<f64> = all_runs.iter().map(|r| r.max_memory).collect();
+                let times: Vec<f64> = all_runs.iter().filter_map(|r| r.cpu_time).collect();
+                let (run_stats, run_curves) = if all_runs.len() > 1 {
+                    let stats = runs::RunStats {
+                        runs: all_runs.len(),
+                        max_memory: runs::spread(maxes.as_slice()),
+                        cpu_time: if times.is_empty() {
+                            None
+                        } else {
+                            Some(runs::spread(times.as_slice()))
+                        },
+                    };
+                    let curves: Vec<&[(f64, f64)]> =
+                        all_runs.iter().map(|r| r.memory.as_slice()).collect();
+                    (Some(stats), Some(runs::combine(curves.as_slice())))
                 } else {
-                    None
+                    (None, None)
                 };
-
-                // load the mem.json file.
-                let json = File::open(&mem_path).map(|mut rdr| {
-                    json::from_reader(&mut rdr as &mut Reader)
-                            .expect(format!("{}/mem.json is not json", hash))
-                }).expect(format!("no {}/mem.json", hash));
-
-                let d: Data = Decodable::decode(&mut json::Decoder::new(json)).unwrap();
-                let simple_mem = simplify_memory_data(d.memory_data.as_slice());
+                let Run { data: d, memory, spikes, quality, .. } =
+                    all_runs.swap_remove(runs::representative(maxes.as_slice())).unwrap();
+                let max_memory = noise::median(maxes.as_slice());
+                let time = if times.is_empty() { None } else { Some(noise::median(times.as_slice())) };
+                let simple_mem = simplify_memory_data(memory.as_slice());
 
                 // if stdout is empty, this should just return nothing
                 let pass_timing = pass_timing(d.stdout.as_slice());
+                let pass_memory = pass_memory::attribute(memory.as_slice(),
+                                                         pass_timing.as_slice());
+                let peak_pass = pass_memory::peak_pass(pass_memory.as_slice());
+
+                // compared with the parent once everything's in order.
+                let growth = growth::growth(memory.as_slice(), pass_memory.as_slice());
 
                 // create & write the output
                 let summary = Summary {
                     hash: hash.clone(),
-                    timestamp: timestamp,
+                    timestamp: commit.timestamp,
                     cpu_time: time,
-                    max_memory: d.max_memory as f64,
-                    pull_request: pull_request
+                    elapsed: Some(d.elapsed),
+                    memory_stats: mem_stats::stats(memory.as_slice()),
+                    max_memory: max_memory,
+                    pull_request: prs.merge,
+                    pull_requests: Some(prs.constituents),
+                    parent: commit.parents.iter().next().map(|p| p.clone()),
+                    sequence: None,
+                    runs: run_stats,
                 };
                 let out = Output {
                     memory_data: simple_mem,
                     pass_timing: pass_timing,
+                    pass_memory: pass_memory,
+                    peak_pass: peak_pass,
+                    quality: quality.clone(),
+                    spikes: spikes,
+                    growth: growth,
+                    run_memory: run_curves,
+                    commit: commit,
                     summary: summary.clone()
                 };
 
@@ -240,7 +629,13 @@ fn main() {
                 let mut out_f = File::create(&fname)
                     .expect(format!("{} can't be opened", fname.display()));
                 out.encode(&mut json::Encoder::new(&mut out_f as &mut Writer)).unwrap();
-                c.send(summary);
+
+                let svg_name = Path::new("out/svg").join(hash + ".svg");
+                let mut svg_f = File::create(&svg_name)
+                    .expect(format!("{} can't be opened", svg_name.display()));
+                svg_f.write_str(svg::profile(out.memory_data.as_slice(),
+                                             out.pass_memory.as_slice()).as_slice()).unwrap();
+                c.send((summary, quality.problems));
             }
         })));
 
@@ -248,23 +643,141 @@ fn main() {
         results.last_mut().unwrap().mut1().get_ref();
     }
 
-    // collect the summaries
+    // collect the summaries, and the commits with dodgy data
+    let mut failures = vec![];
     for (p, r) in results.into_iter() {
         match r.unwrap() {
             Err(_) => {} // it failed
             Ok(_) => {
-                summary.push(p.recv());
+                let (s, problems) = p.recv();
+                if !problems.is_empty() {
+                    failures.push(validate::Failure { hash: s.hash.clone(), problems: problems });
+                }
+                summary.push(s);
             }
         }
     }
 
-    summary.sort_by(|x, y| if *x < *y {Less} else if *x == *y {Equal} else {Greater});
+    // the things we've just processed have their parents, but a
+    // repository helps if older entries don't, or aren't adjacent.
+    let (head, extra_parents) = match config.git {
+        Some(ref repo) => git::first_parents(repo, "master"),
+        None => (None, HashMap::new())
+    };
+    let summary = order::order(summary, head, &extra_parents);
+
+    write_json_lines(&summary_path, summary.as_slice());
+    let mut summary_svg = File::create(&Path::new("out/summary.svg"))
+        .expect("can't write to summary.svg".into_string());
+    summary_svg.write_str(svg::summary(summary.as_slice()).as_slice()).unwrap();
+
+    // only this run's failures: the older ones are in their outputs.
+    if !failures.is_empty() {
+        println!("{} commits failed data checks; see out/quality_report.json", failures.len());
+    }
+    write_json_lines(&Path::new("out/quality_report.json"), failures.as_slice());
+
+    let regressions = regressions::detect(summary.as_slice());
+    write_json_lines(&Path::new("out/regressions.json"), regressions.as_slice());
+
+    let baselines = noise::baselines(summary.as_slice());
+    write_json_lines(&Path::new("out/baseline.json"), baselines.as_slice());
+
+    let mut outputs = load_outputs(summary.as_slice());
+    let passes = pass_history::series(summary.as_slice(), |hash| outputs.find(&hash));
+    let pass_regressions = pass_history::detect(&passes);
+    write_json_lines(&Path::new("out/pass_regressions.json"), pass_regressions.as_slice());
+
+    let changes: Vec<regressions::Regression> =
+        regressions.iter().chain(pass_regressions.iter()).map(|r| r.clone()).collect();
+    let mut feed_f = File::create(&Path::new("out/feed.xml"))
+        .expect("can't write to feed.xml".into_string());
+    feed_f.write_str(feed::feed(summary.as_slice(), changes.as_slice()).as_slice()).unwrap();
+
+    let heatmap = heatmap::heatmap(summary.as_slice(), config.heatmap, |hash| outputs.find(&hash));
+    let mut heatmap_f = File::create(&Path::new("out/heatmap.json"))
+        .expect("can't write to heatmap".into_string());
+    heatmap_f.write_str(json::encode(&heatmap).as_slice()).unwrap();
+
+    // each commit against its parent, for its growth, its pages, its
+    // aligned difference and the impact of each PR that landed in it.
+    // That's only redone where it could have changed: for the new
+    // commits, those with a different parent (e.g. the ones just after
+    // a new commit), those whose baseline moved, and any that were
+    // never written.
+    let bands = perf_impact::baselines(summary.as_slice(), &passes);
+    let old_passes = pass_history::series(old_summary.as_slice(), |hash| outputs.find(&hash));
+    let old_bands = perf_impact::baselines(old_summary.as_slice(), &old_passes);
+    let old_parents: HashMap<&str, Option<&str>> = old_summary.iter()
+        .zip(order::parents(old_summary.as_slice()).into_iter())
+        .map(|(s, p)| (s.hash.as_slice(), p.map(|i| old_summary[i].hash.as_slice())))
+        .collect();
+
+    let no_bands = TreeMap::new();
+    let mut merged_as: HashMap<String, Vec<uint>> = HashMap::new();
+    for (pr, hash) in pull_requests::merge_commits().into_iter() {
+        merged_as.find_or_insert_with(hash, |_| vec![]).push(pr);
+    }
+    pages::create_dirs();
+    fs::mkdir_recursive(&Path::new("out/aligned"), io::USER_RWX)
+        .expect("can't create out/aligned".into_string());
+    for (s, parent) in summary.iter().zip(order::parents(summary.as_slice()).into_iter()) {
+        let hash = s.hash.as_slice();
+        let parent = parent.map(|i| summary[i].hash.as_slice());
+        let unchanged = old_parents.find(&hash) == Some(&parent)
+            && old_bands.find(&s.hash) == bands.find(&s.hash)
+            && Path::new("commit").join(s.hash.clone() + ".html").exists()
+            && (parent.is_none() || Path::new("out/aligned").join(s.hash.clone() + ".json").exists());
+        if unchanged || !outputs.contains_key(&hash) { continue }
+
+        let grown = match parent.and_then(|p| outputs.find(&p).map(|p_out| (p, p_out))) {
+            Some((p, p_out)) => growth::update(outputs.find(&hash).unwrap(), p, p_out),
+            None => None
+        };
+        match grown {
+            Some(out) => {
+                write_output(hash, &out);
+                outputs.insert(hash, out);
+            }
+            None => {}
+        }
 
-    let mut w = io::MemWriter::new();
-    summary.encode(&mut json::Encoder::new(&mut w as &mut Writer)).unwrap();
-    let text = String::from_utf8(w.unwrap()).expect("Non-utf8 JSON written".into_string());
+        let out = outputs.find(&hash).unwrap();
+        let parent = parent.and_then(|p| {
+            outputs.find(&p).map(|p_out| diff::diff(p, p_out, hash, out))
+        });
+        pages::write(s, out, parent.as_ref());
+        match parent {
+            Some(ref d) => {
+                let aligned = align::Aligned {
+                    parent: d.a.clone(),
+                    aligned_memory: d.aligned_memory.clone(),
+                };
+                let path = Path::new("out/aligned").join(s.hash.clone() + ".json");
+                let mut f = File::create(&path).expect(format!("can't write to {}", path.display()));
+                f.write_str(json::encode(&aligned).as_slice()).unwrap();
+
+                let bands = bands.find(&s.hash).unwrap_or(&no_bands);
+                let metadata = merged_as.find(&s.hash).map(|prs| prs.as_slice()).unwrap_or(&[]);
+                for pr in perf_impact::landed(s, metadata).into_iter() {
+                    // like the pages, a constituent is marked as part
+                    // of the rollup that merged it.
+                    let rollup = match s.pull_request {
+                        Some(m) if m != pr => Some(m),
+                        _ => None
+                    };
+                    perf_impact::write(&perf_impact::impact(pr, rollup, d, bands));
+                }
+            }
+            None => {}
+        }
+    }
+}
 
-    let mut summary_f = File::create(&summary_path).expect("can't write to summary".into_string());
-    // put one commit a line, so the diffs are smaller.
-    summary_f.write(text.replace("{", "\n{").replace("]", "\n]").as_bytes()).unwrap();
+/// Write a JSON list with one element a line, so the diffs are
+/// smaller.
+fn write_json_lines<'a, T: Encodable<json::Encoder<'a>, IoError>>(path: &Path, xs: &[T]) {
+    let mut f = File::create(path).expect(format!("can't write to {}", path.display()));
+    let lines: Vec<String> = xs.iter().map(|x| json::encode(x)).collect();
+    f.write_str(format!("[\n{}\n]", lines.connect(",\n")).as_slice()).unwrap();
 }
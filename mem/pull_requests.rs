@@ -0,0 +1,42 @@
+//! Reading the per-PR metadata in ../pull_requests, which is kept
+//! up to date by the scripts there.
+
+use std::collections::TreeMap;
+use std::io::{fs, File};
+use std::io::fs::PathExtensions;
+
+/// The directory holding `pr`'s metadata, e.g. pull_requests/123/12345.
+pub fn dir(pr: uint) -> Path {
+    Path::new(format!("../pull_requests/{:02u}/{}", pr / 100, pr))
+}
+
+/// The title of `pr`, if we've got it.
+pub fn title(pr: uint) -> Option<String> {
+    read(pr, "title.txt")
+}
+
+/// The merge commit of every PR with a merge_commit.txt.
+pub fn merge_commits() -> TreeMap<uint, String> {
+    let mut ret = TreeMap::new();
+    let groups = fs::readdir(&Path::new("../pull_requests")).unwrap_or(vec![]);
+    for group in groups.iter().filter(|p| p.is_dir()) {
+        for dir in fs::readdir(group).unwrap_or(vec![]).iter() {
+            let pr = match dir.filename_str().and_then(|f| from_str::<uint>(f)) {
+                Some(pr) => pr,
+                None => continue
+            };
+            match read(pr, "merge_commit.txt") {
+                Some(hash) => { ret.insert(pr, hash); }
+                None => {}
+            }
+        }
+    }
+    ret
+}
+
+/// The contents of one of `pr`'s files, trimmed.
+fn read(pr: uint, name: &str) -> Option<String> {
+    File::open(&dir(pr).join(name)).read_to_string().ok()
+        .map(|s| s.as_slice().trim().into_string())
+        .and_then(|s| if s.is_empty() { None } else { Some(s) })
+}
@@ -0,0 +1,71 @@
+//! Looking for the commits that moved the summary metrics.
+
+use std::fmt;
+use changepoint;
+use diff;
+use {Summary, METRICS};
+
+/// A commit where one of the metrics changed level.
+#[deriving(Encodable, Decodable, Clone)]
+pub struct Regression {
+    pub hash: String,
+    pub pull_request: Option<uint>,
+    pub pull_requests: Option<Vec<uint>>,
+    pub metric: String,
+    pub before: f64,
+    pub after: f64,
+    /// (after - before) / before, or None if before is 0 (as many
+    /// passes' median times are).
+    pub change: Option<f64>,
+    pub confidence: f64,
+    /// false for an improvement.
+    pub regression: bool,
+}
+
+impl fmt::Show for Regression {
+    /// e.g. `typechecking +12.0% at #1234 (0123abcd)`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{} {} at ", self.metric, diff::percent(self.change, self.after)));
+        match self.pull_request {
+            Some(pr) => try!(write!(f, "#{} ", pr)),
+            None => {}
+        }
+        write!(f, "({})", self.hash.as_slice().slice_to(8))
+    }
+}
+
+/// Run change-point detection over each metric of `summary`, which
+/// should be in order.
+pub fn detect(summary: &[Summary]) -> Vec<Regression> {
+    let mut ret = vec![];
+    for &metric in METRICS.iter() {
+        // not every commit has every metric, so remember where each
+        // point came from.
+        let points: Vec<(&Summary, f64)> = summary.iter()
+            .filter_map(|s| s.metric(metric).map(|x| (s, x)))
+            .collect();
+        ret.extend(detect_series(metric, points.as_slice()).into_iter());
+    }
+    ret
+}
+
+/// Run change-point detection over a series of values of `metric`,
+/// in order, labelled with the commit they came from.
+pub fn detect_series(metric: &str, points: &[(&Summary, f64)]) -> Vec<Regression> {
+    let xs: Vec<f64> = points.iter().map(|&(_, x)| x).collect();
+    changepoint::detect(xs.as_slice()).into_iter().map(|c| {
+        let (s, _) = points[c.index];
+        Regression {
+            hash: s.hash.clone(),
+            pull_request: s.pull_request,
+            pull_requests: s.pull_requests.clone(),
+            metric: metric.into_string(),
+            before: c.before,
+            after: c.after,
+            change: if c.before == 0.0 { None } else { Some((c.after - c.before) / c.before) },
+            confidence: c.confidence,
+            // everything we measure is better when smaller.
+            regression: c.after > c.before,
+        }
+    }).collect()
+}
@@ -0,0 +1,85 @@
+//! Putting memory traces onto a common, evenly spaced time axis, so
+//! that traces sampled at different times can be averaged,
+//! subtracted and compared point by point.
+
+use std::cmp;
+
+/// The times to resample at.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Grid {
+    /// Every this many seconds from 0 to the end of the trace.
+    Seconds(f64),
+    /// This many points evenly spread from 0% to 100% of the trace's
+    /// length; the resampled times are then percentages.
+    Percent(uint),
+}
+
+/// How to fill in between samples.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Interpolation {
+    /// The last sample at or before the time, which is what the
+    /// memory actually was as far as we know.
+    Step,
+    /// A straight line between the samples either side.
+    Linear,
+}
+
+/// Resample `xs` onto `grid`.
+pub fn resample(xs: &[(f64, f64)], grid: Grid, interp: Interpolation) -> Vec<(f64, f64)> {
+    if xs.is_empty() { return vec![] }
+    let (end, _) = xs[xs.len() - 1];
+
+    match grid {
+        Seconds(every) => {
+            let n = (end / every).floor() as uint + 1;
+            range(0, n).map(|i| {
+                let t = every * i as f64;
+                (t, at(xs, t, interp))
+            }).collect()
+        }
+        Percent(n) => {
+            evenly(0.0, end, n).into_iter().enumerate().map(|(i, t)| {
+                (100.0 * i as f64 / cmp::max(n - 1, 1) as f64, at(xs, t, interp))
+            }).collect()
+        }
+    }
+}
+
+/// `n` evenly spaced times from `start` to `end` inclusive.
+pub fn evenly(start: f64, end: f64, n: uint) -> Vec<f64> {
+    if n == 1 { return vec![start] }
+    let step = (end - start) / (n - 1) as f64;
+    range(0, n).map(|i| start + step * i as f64).collect()
+}
+
+/// The value of `xs` at each of `ts`.
+pub fn sample(xs: &[(f64, f64)], ts: &[f64], interp: Interpolation) -> Vec<f64> {
+    ts.iter().map(|&t| at(xs, t, interp)).collect()
+}
+
+/// The value of `xs` at `t`, clamping outside its ends.
+pub fn at(xs: &[(f64, f64)], t: f64, interp: Interpolation) -> f64 {
+    match interp {
+        Step => step(xs, t),
+        Linear => linear(xs, t),
+    }
+}
+
+/// The value of the last sample of `xs` at or before `t` (or the
+/// first, if `t` is before them all).
+pub fn step(xs: &[(f64, f64)], t: f64) -> f64 {
+    let i = xs.iter().position(|&(x, _)| x > t).unwrap_or(xs.len());
+    let (_, y) = xs[if i == 0 { 0 } else { i - 1 }];
+    y
+}
+
+/// The value of the piecewise linear curve through `xs` at `t`,
+/// clamping outside its ends.
+pub fn linear(xs: &[(f64, f64)], t: f64) -> f64 {
+    if xs.len() == 1 { let (_, y) = xs[0]; return y }
+
+    // the segment containing t, or the end one nearest it.
+    let i = cmp::max(1, xs.iter().position(|&(x, _)| x >= t).unwrap_or(xs.len() - 1));
+    let ((x0, y0), (x1, y1)) = (xs[i - 1], xs[i]);
+    if t <= x0 { y0 } else if t >= x1 { y1 } else { y0 + (y1 - y0) * (t - x0) / (x1 - x0) }
+}
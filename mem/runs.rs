@@ -0,0 +1,100 @@
+//! Combining several runs of the benchmark on the same commit, so
+//! that noise can be told apart from real change.
+
+use std::f64;
+use resample::{mod, linear};
+use noise;
+
+/// The number of points in a combined curve.
+static CURVE_POINTS: uint = 500;
+
+/// Two-sided 95% Student's t critical values, for 1 to 30 degrees of
+/// freedom. Above that, the normal distribution's 1.96 is fine.
+static T_95: [f64, .. 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+    2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+/// How a metric varied across runs.
+#[deriving(Encodable, Decodable, Clone, PartialEq, Show)]
+pub struct Spread {
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+    /// a 95% confidence interval for the mean
+    pub ci_low: f64,
+    pub ci_high: f64,
+}
+
+#[deriving(Encodable, Decodable, Clone, PartialEq, Show)]
+pub struct RunStats {
+    pub runs: uint,
+    pub max_memory: Spread,
+    /// None if none of the runs were timed.
+    pub cpu_time: Option<Spread>,
+}
+
+/// The memory curves of all the runs, combined on a common time axis.
+#[deriving(Encodable, Decodable, Clone, Show)]
+pub struct RunCurves {
+    pub median: Vec<(f64, f64)>,
+    /// the least and most memory any run used at each time
+    pub low: Vec<(f64, f64)>,
+    pub high: Vec<(f64, f64)>,
+}
+
+/// The spread of `xs`, which must be non-empty.
+pub fn spread(xs: &[f64]) -> Spread {
+    let n = xs.len();
+    let mean = xs.iter().fold(0.0, |a, &x| a + x) / n as f64;
+    let half_width = if n < 2 {
+        0.0
+    } else {
+        let var = xs.iter().fold(0.0, |a, &x| a + (x - mean) * (x - mean)) / (n - 1) as f64;
+        let t = if n - 1 <= T_95.len() { T_95[n - 2] } else { 1.96 };
+        t * (var / n as f64).sqrt()
+    };
+    Spread {
+        min: xs.iter().fold(f64::INFINITY, |a, &x| a.min(x)),
+        median: noise::median(xs),
+        max: xs.iter().fold(f64::NEG_INFINITY, |a, &x| a.max(x)),
+        ci_low: mean - half_width,
+        ci_high: mean + half_width,
+    }
+}
+
+/// The index of the run whose value is the median of `xs` (the
+/// lower of the middle two, for an even number), to stand in for
+/// all of them where a single run is needed.
+pub fn representative(xs: &[f64]) -> uint {
+    let mut order: Vec<uint> = range(0, xs.len()).collect();
+    order.sort_by(|&i, &j| xs[i].partial_cmp(&xs[j]).unwrap_or(Equal));
+    order[(xs.len() - 1) / 2]
+}
+
+/// Combine the memory traces of several runs onto an evenly spaced
+/// time axis. At each time, only the runs still going are counted.
+pub fn combine(curves: &[&[(f64, f64)]]) -> RunCurves {
+    let curves: Vec<&[(f64, f64)]> = curves.iter().filter(|c| !c.is_empty()).map(|&c| c).collect();
+    let mut ret = RunCurves { median: vec![], low: vec![], high: vec![] };
+    if curves.is_empty() { return ret }
+
+    let start = curves.iter().fold(f64::INFINITY, |a, c| { let (t, _) = c[0]; a.min(t) });
+    let end = curves.iter().fold(f64::NEG_INFINITY, |a, c| {
+        let (t, _) = c[c.len() - 1];
+        a.max(t)
+    });
+    for t in resample::evenly(start, end, CURVE_POINTS).into_iter() {
+        let ms: Vec<f64> = curves.iter()
+            .filter(|c| { let (s, _) = c[0]; let (e, _) = c[c.len() - 1]; s <= t && t <= e })
+            .map(|c| linear(*c, t))
+            .collect();
+        if ms.is_empty() { continue }
+        let s = spread(ms.as_slice());
+        ret.median.push((t, s.median));
+        ret.low.push((t, s.min));
+        ret.high.push((t, s.max));
+    }
+    ret
+}
@@ -215,6 +215,12 @@ function TextDetail(hash, data) {
     }
     ul.appendChild(l);
   }
+  var prs = data.summary.pull_requests;
+  if (prs && prs.length > 1) {
+    ul.appendChild(li('Rolled up: ' + prs.map(function(pr) {
+      return '<a href="https://github.com/rust-lang/rust/pull/' + pr + '">#' + pr + '</a>';
+    }).join(', '), 'rollup-text'));
+  }
 
   var date = new Date(data.summary.timestamp * 1000)
              .toISOString().replace(/\.[0-9]{3}Z/, '').replace('T', ' ');
@@ -223,6 +229,9 @@ function TextDetail(hash, data) {
     li('Max memory usage: ' + (data.summary.max_memory/(1024*1024)).toFixed(0) + ' MiB', 'mem-text'))
   var cpu_text = data.summary.cpu_time ? (data.summary.cpu_time.toFixed(1) + ' s') : 'not measured';
   ul.appendChild(li('CPU time: ' + cpu_text, 'cpu-text'));
+  if (data.quality && data.quality.problems.length) {
+    ul.appendChild(li('Suspect data: ' + data.quality.problems.join('; '), 'quality-text'));
+  }
 
   var controls = document.createElement('div');
   controls.classList.add('text-detail-controls');
@@ -0,0 +1,157 @@
+//! Finding commits whose memory profiles look alike, to answer "when
+//! did it last look like this?", and grouping the history into eras
+//! of similar profiles.
+//!
+//! Each commit becomes a vector: its memory curve resampled over
+//! 0-100% of the run and divided by its peak (so only the shape
+//! counts), followed by the fraction of the total time spent in each
+//! pass. Commits are compared by the Euclidean distance between
+//! these.
+
+use std::f64;
+use serialize::json::Json;
+use diff;
+use pass_history;
+use resample::{mod, Linear, Percent};
+use Summary;
+
+/// The number of points each curve is resampled to.
+static CURVE_POINTS: uint = 100;
+/// How much the pass timings count relative to the curve's shape.
+static PASS_WEIGHT: f64 = 1.0;
+/// k-means stops after this many rounds even if it hasn't settled.
+static MAX_ROUNDS: uint = 50;
+
+/// The features of one commit.
+pub struct Profile<'a> {
+    pub summary: &'a Summary,
+    pub features: Vec<f64>,
+}
+
+/// A commit similar to the one asked about.
+#[deriving(Encodable)]
+pub struct Match {
+    pub hash: String,
+    pub pull_request: Option<uint>,
+    pub distance: f64,
+}
+
+/// A commit's era.
+#[deriving(Encodable)]
+pub struct Cluster {
+    pub hash: String,
+    pub sequence: Option<uint>,
+    pub cluster: uint,
+}
+
+/// Build the profile of each commit of `summary` that has a memory
+/// trace, using `load` to read out/<hash>.json.
+pub fn profiles<'a>(summary: &'a [Summary], load: |&str| -> Option<Json>) -> Vec<Profile<'a>> {
+    let mut raw = vec![];
+    let mut names = vec![];
+    for s in summary.iter() {
+        let out = match load(s.hash.as_slice()) {
+            Some(o) => o,
+            None => continue
+        };
+        let memory = diff::memory_data(&out);
+        if memory.is_empty() { continue }
+
+        let peak = memory.iter().fold(0.0, |a, &(_, m)| a.max(m));
+        let curve: Vec<f64> = resample::resample(memory.as_slice(), Percent(CURVE_POINTS), Linear)
+            .into_iter()
+            .map(|(_, m)| if peak > 0.0 { m / peak } else { 0.0 })
+            .collect();
+
+        let passes = pass_history::pass_timing(&out);
+        for name in passes.keys() {
+            if !names.contains(name) { names.push(name.clone()) }
+        }
+        raw.push((s, curve, passes));
+    }
+    names.sort();
+
+    raw.into_iter().map(|(s, curve, passes)| {
+        // scaled so the distance between curves is an RMS.
+        let scale = 1.0 / (CURVE_POINTS as f64).sqrt();
+        let mut features: Vec<f64> = curve.into_iter().map(|m| m * scale).collect();
+
+        let total = passes.values().fold(0.0, |a, &t| a + t);
+        features.extend(names.iter().map(|name| {
+            let t = passes.find(name).map(|t| *t).unwrap_or(0.0);
+            if total > 0.0 { PASS_WEIGHT * t / total } else { 0.0 }
+        }));
+        Profile { summary: s, features: features }
+    }).collect()
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).fold(0.0, |acc, (x, y)| acc + (x - y) * (x - y)).sqrt()
+}
+
+/// The `k` commits most like `hash`, closest first.
+pub fn nearest(profiles: &[Profile], hash: &str, k: uint) -> Option<Vec<Match>> {
+    let target = match profiles.iter().find(|p| p.summary.hash.as_slice() == hash) {
+        Some(t) => t,
+        None => return None
+    };
+    let mut matches: Vec<Match> = profiles.iter()
+        .filter(|p| p.summary.hash.as_slice() != hash)
+        .map(|p| Match {
+            hash: p.summary.hash.clone(),
+            pull_request: p.summary.pull_request,
+            distance: distance(target.features.as_slice(), p.features.as_slice()),
+        })
+        .collect();
+    matches.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(Equal));
+    matches.truncate(k);
+    Some(matches)
+}
+
+/// Group the commits into `k` clusters with k-means. The clusters
+/// start at evenly spaced points in history, so they tend to come out
+/// numbered in the order the eras started.
+pub fn cluster(profiles: &[Profile], k: uint) -> Vec<Cluster> {
+    let n = profiles.len();
+    if n == 0 || k == 0 { return vec![] }
+
+    let mut centroids: Vec<Vec<f64>> = range(0, k).map(|i| {
+        profiles[i * n / k].features.clone()
+    }).collect();
+    let mut labels = Vec::from_elem(n, 0u);
+
+    for round in range(0, MAX_ROUNDS) {
+        let mut changed = false;
+        for (i, p) in profiles.iter().enumerate() {
+            let (mut best_distance, mut best) = (f64::INFINITY, 0);
+            for (j, c) in centroids.iter().enumerate() {
+                let d = distance(p.features.as_slice(), c.as_slice());
+                if d < best_distance {
+                    best_distance = d;
+                    best = j;
+                }
+            }
+            if labels[i] != best {
+                *labels.get_mut(i) = best;
+                changed = true;
+            }
+        }
+        if !changed && round > 0 { break }
+
+        for (j, c) in centroids.iter_mut().enumerate() {
+            let members: Vec<&Profile> = profiles.iter().zip(labels.iter())
+                .filter(|&(_, &l)| l == j)
+                .map(|(p, _)| p)
+                .collect();
+            // an empty cluster keeps its old centre
+            if members.is_empty() { continue }
+            for (d, x) in c.iter_mut().enumerate() {
+                *x = members.iter().fold(0.0, |a, p| a + p.features[d]) / members.len() as f64;
+            }
+        }
+    }
+
+    profiles.iter().zip(labels.into_iter()).map(|(p, l)| {
+        Cluster { hash: p.summary.hash.clone(), sequence: p.summary.sequence, cluster: l }
+    }).collect()
+}
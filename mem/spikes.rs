@@ -0,0 +1,69 @@
+//! Finding the single-sample glitches the sampler sometimes records,
+//! where memory jumps by gigabytes and immediately comes back.
+//!
+//! This is a Hampel filter: a sample is an outlier if it is too many
+//! MADs from the median of its neighbours. Only a lone outlier is a
+//! glitch, so both the samples either side of it have to agree with
+//! the median: a genuine short peak lasts more than one sample, and
+//! is exactly what max_memory should see.
+
+use std::cmp;
+use noise;
+
+/// The number of neighbours on each side to compare with.
+static HALF_WINDOW: uint = 3;
+/// How many (scaled) MADs from the local median counts as a spike.
+static MADS: f64 = 3.0;
+/// Flat regions have a MAD of zero, so also require a jump of at
+/// least this many bytes.
+static MIN_JUMP: f64 = 64.0 * 1024.0 * 1024.0;
+
+/// What to do with spikes.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Action {
+    /// Leave the data alone.
+    Ignore,
+    /// Record them, but keep them in the data.
+    Flag,
+    /// Record them and take them out of the data.
+    Remove,
+}
+
+/// The spikes found in a trace.
+#[deriving(Encodable, Decodable, Clone, Show)]
+pub struct Spikes {
+    /// Whether `points` were taken out of the data.
+    pub removed: bool,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// Look for spikes in `xs`, returning the data to carry on with and
+/// what was found.
+pub fn filter(xs: &[(f64, f64)], action: Action) -> (Vec<(f64, f64)>, Option<Spikes>) {
+    if action == Ignore { return (xs.to_vec(), None) }
+
+    let mut kept = vec![];
+    let mut spikes = vec![];
+    for (i, &(t, m)) in xs.iter().enumerate() {
+        let lo = if i > HALF_WINDOW { i - HALF_WINDOW } else { 0 };
+        let hi = cmp::min(i + HALF_WINDOW + 1, xs.len());
+        let window: Vec<f64> = xs.slice(lo, hi).iter().map(|&(_, m)| m).collect();
+        let band = noise::band(window.as_slice());
+
+        let threshold = MIN_JUMP.max(MADS * 1.4826 * band.mad);
+        let agrees = |j: uint| {
+            let (_, n) = xs[j];
+            (n - band.median).abs() <= threshold
+        };
+        let lone = i > 0 && i + 1 < xs.len() && agrees(i - 1) && agrees(i + 1);
+        if (m - band.median).abs() > threshold && lone {
+            spikes.push((t, m));
+        } else {
+            kept.push((t, m));
+        }
+    }
+
+    let removed = action == Remove;
+    let data = if removed { kept } else { xs.to_vec() };
+    (data, Some(Spikes { removed: removed, points: spikes }))
+}
@@ -0,0 +1,158 @@
+//! Drawing the charts as static SVG, for places without JavaScript:
+//! the text-only view, generated pages and issue comments.
+
+use pass_memory::PassMemory;
+use {Summary, MIB};
+
+static WIDTH: f64 = 870.0;
+static HEIGHT: f64 = 300.0;
+static MARGIN_LEFT: f64 = 60.0;
+static MARGIN_RIGHT: f64 = 60.0;
+static MARGIN_TOP: f64 = 10.0;
+static MARGIN_BOTTOM: f64 = 30.0;
+/// The height of each bar in the pass timing chart.
+static BAR_HEIGHT: f64 = 16.0;
+/// Only this many of the longest passes get a bar.
+static MAX_BARS: uint = 15;
+/// Passes shorter than this fraction of the run don't get a boundary
+/// line, since it'd just be clutter.
+static MIN_BOUNDARY_FRACTION: f64 = 0.02;
+
+/// Escape `s` for use in XML text or attributes.
+pub fn escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&#39;"),
+            c => ret.push(c)
+        }
+    }
+    ret
+}
+
+/// Maps data coordinates onto the plotting area.
+struct Scale { lo: f64, hi: f64, out_lo: f64, out_hi: f64 }
+impl Scale {
+    fn new(lo: f64, hi: f64, out_lo: f64, out_hi: f64) -> Scale {
+        // avoid dividing by zero for a flat series
+        let hi = if hi > lo { hi } else { lo + 1.0 };
+        Scale { lo: lo, hi: hi, out_lo: out_lo, out_hi: out_hi }
+    }
+    fn at(&self, x: f64) -> f64 {
+        self.out_lo + (x - self.lo) / (self.hi - self.lo) * (self.out_hi - self.out_lo)
+    }
+}
+
+fn polyline(points: &[(f64, f64)], x: &Scale, y: &Scale, class: &str, colour: &str) -> String {
+    let coords: Vec<String> = points.iter()
+        .map(|&(a, b)| format!("{:.1f},{:.1f}", x.at(a), y.at(b)))
+        .collect();
+    format!("<polyline class=\"{}\" stroke=\"{}\" fill=\"none\" points=\"{}\"/>\n",
+            class, colour, coords.connect(" "))
+}
+
+/// Five labelled ticks up the y axis, on the left or the right.
+fn y_axis(y: &Scale, right: bool, label: &str, unit: |f64| -> String) -> String {
+    let x = if right { WIDTH - MARGIN_RIGHT } else { MARGIN_LEFT };
+    let (anchor, dx) = if right { ("start", 4.0) } else { ("end", -4.0) };
+    let mut ret = format!("<line class=\"axis\" x1=\"{0:.1f}\" y1=\"{1:.1f}\" x2=\"{0:.1f}\" y2=\"{2:.1f}\" \
+                           stroke=\"black\"/>\n",
+                          x, MARGIN_TOP, HEIGHT - MARGIN_BOTTOM);
+    for i in range(0u, 5) {
+        let v = y.lo + (y.hi - y.lo) * i as f64 / 4.0;
+        ret.push_str(format!("<text x=\"{:.1f}\" y=\"{:.1f}\" text-anchor=\"{}\" font-size=\"10\">{}</text>\n",
+                             x + dx, y.at(v), anchor, unit(v)).as_slice());
+    }
+    let lx = if right { WIDTH - 10.0 } else { 14.0 };
+    ret.push_str(format!("<text x=\"{0:.1f}\" y=\"{1:.1f}\" transform=\"rotate(-90 {0:.1f} {1:.1f})\" \
+                          text-anchor=\"middle\" font-size=\"11\">{2}</text>\n",
+                         lx, (MARGIN_TOP + HEIGHT - MARGIN_BOTTOM) / 2.0, escape(label)).as_slice());
+    ret
+}
+
+fn header(height: f64) -> String {
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\">\n",
+            WIDTH, height)
+}
+
+/// A commit's memory curve with the pass boundaries marked on it,
+/// and a bar chart of the longest passes below.
+pub fn profile(memory: &[(f64, f64)], passes: &[PassMemory]) -> String {
+    let mut bars: Vec<&PassMemory> = passes.iter().collect();
+    bars.sort_by(|a, b| (b.end - b.start).partial_cmp(&(a.end - a.start)).unwrap_or(Equal));
+    bars.truncate(MAX_BARS);
+
+    let bars_top = HEIGHT + 10.0;
+    let total_height = bars_top + BAR_HEIGHT * bars.len() as f64 + 10.0;
+    let mut svg = header(total_height);
+
+    let end = memory.iter().fold(0.0, |a, &(t, _)| a.max(t));
+    let end = passes.iter().fold(end, |a, p| a.max(p.end));
+    let peak = memory.iter().fold(0.0, |a, &(_, m)| a.max(m));
+    let x = Scale::new(0.0, end, MARGIN_LEFT, WIDTH - MARGIN_RIGHT);
+    let y = Scale::new(0.0, peak, HEIGHT - MARGIN_BOTTOM, MARGIN_TOP);
+
+    for p in passes.iter() {
+        if p.end - p.start < MIN_BOUNDARY_FRACTION * end { continue }
+        svg.push_str(format!("<line class=\"pass\" x1=\"{0:.1f}\" y1=\"{1:.1f}\" x2=\"{0:.1f}\" \
+                              y2=\"{2:.1f}\" stroke=\"#ccc\"><title>{3}</title></line>\n",
+                             x.at(p.start), MARGIN_TOP, HEIGHT - MARGIN_BOTTOM,
+                             escape(p.name.as_slice())).as_slice());
+    }
+    svg.push_str(polyline(memory, &x, &y, "memory", "steelblue").as_slice());
+    svg.push_str(y_axis(&y, false, "Memory (MiB)", |v| format!("{:.0f}", v / MIB)).as_slice());
+    svg.push_str(format!("<text x=\"{:.1f}\" y=\"{:.1f}\" text-anchor=\"middle\" font-size=\"11\">\
+                          Time (s), 0 to {:.1f}</text>\n",
+                         WIDTH / 2.0, HEIGHT - 8.0, end).as_slice());
+
+    let longest = bars.iter().fold(0.0, |a, p| a.max(p.end - p.start));
+    let bar_x = Scale::new(0.0, longest, WIDTH / 3.0, WIDTH - MARGIN_RIGHT);
+    for (i, p) in bars.iter().enumerate() {
+        let top = bars_top + BAR_HEIGHT * i as f64;
+        svg.push_str(format!("<text x=\"{:.1f}\" y=\"{:.1f}\" text-anchor=\"end\" font-size=\"11\">{}</text>\n",
+                             WIDTH / 3.0 - 4.0, top + BAR_HEIGHT - 4.0,
+                             escape(p.name.as_slice())).as_slice());
+        svg.push_str(format!("<rect class=\"pass-bar\" x=\"{:.1f}\" y=\"{:.1f}\" width=\"{:.1f}\" \
+                              height=\"{:.1f}\" fill=\"steelblue\"/>\n",
+                             WIDTH / 3.0, top + 2.0, bar_x.at(p.end - p.start) - WIDTH / 3.0,
+                             BAR_HEIGHT - 4.0).as_slice());
+        svg.push_str(format!("<text x=\"{:.1f}\" y=\"{:.1f}\" font-size=\"10\">{:.2f}s</text>\n",
+                             bar_x.at(p.end - p.start) + 4.0, top + BAR_HEIGHT - 4.0,
+                             p.end - p.start).as_slice());
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// max_memory and cpu_time of each commit, evenly spaced in order.
+pub fn summary(summary: &[Summary]) -> String {
+    let mut svg = header(HEIGHT);
+    let mem: Vec<(f64, f64)> = summary.iter().enumerate()
+        .map(|(i, s)| (i as f64, s.max_memory))
+        .collect();
+    let cpu: Vec<(f64, f64)> = summary.iter().enumerate()
+        .filter_map(|(i, s)| s.cpu_time.map(|t| (i as f64, t)))
+        .collect();
+
+    let x = Scale::new(0.0, summary.len() as f64 - 1.0, MARGIN_LEFT, WIDTH - MARGIN_RIGHT);
+    let mem_y = Scale::new(0.0, mem.iter().fold(0.0, |a, &(_, m)| a.max(m)),
+                           HEIGHT - MARGIN_BOTTOM, MARGIN_TOP);
+    let cpu_y = Scale::new(0.0, cpu.iter().fold(0.0, |a, &(_, t)| a.max(t)),
+                           HEIGHT - MARGIN_BOTTOM, MARGIN_TOP);
+
+    svg.push_str(polyline(mem.as_slice(), &x, &mem_y, "mem", "steelblue").as_slice());
+    svg.push_str(polyline(cpu.as_slice(), &x, &cpu_y, "cpu", "darkorange").as_slice());
+    svg.push_str(y_axis(&mem_y, false, "Max memory (MiB)", |v| format!("{:.0f}", v / MIB)).as_slice());
+    svg.push_str(y_axis(&cpu_y, true, "CPU Time (s)", |v| format!("{:.0f}", v)).as_slice());
+    svg.push_str(format!("<text x=\"{:.1f}\" y=\"{:.1f}\" text-anchor=\"middle\" font-size=\"11\">\
+                          {} merges, oldest on the left</text>\n",
+                         WIDTH / 2.0, HEIGHT - 8.0, summary.len()).as_slice());
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,188 @@
+//! Parsing of time.txt, which has turned up in a few different
+//! shapes depending on the machine that ran the benchmark.
+//!
+//! Supported forms:
+//!
+//! - GNU time's default output: `83.48user 1.23system 1:25.67elapsed ...`
+//! - the same with a comma decimal separator: `83,48user 1,23system ...`
+//! - bash's `time` keyword: `real 1m25.67s` / `user 1m23.48s` / `sys 0m1.23s`
+//! - POSIX `time -p`: `real 85.67` / `user 83.48` / `sys 1.23`
+
+use std::fmt;
+
+/// The times (in seconds) recorded in a time.txt.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Time {
+    pub user: f64,
+    pub system: f64,
+    pub elapsed: Option<f64>,
+}
+
+impl Time {
+    /// Total CPU time, i.e. user + system.
+    pub fn cpu_time(&self) -> f64 {
+        self.user + self.system
+    }
+}
+
+/// Reasons a time.txt can't be understood.
+#[deriving(Clone, PartialEq)]
+pub enum TimeError {
+    /// The named field was never found anywhere in the file.
+    Missing(&'static str),
+    /// The named field was found on this line, but its value wasn't
+    /// a duration we can read.
+    BadValue(&'static str, String),
+}
+
+impl fmt::Show for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Missing(field) => write!(f, "time is formatted wrong: missing {}", field),
+            BadValue(field, ref line) => {
+                write!(f, "time is formatted wrong: can't read {} in {}", field, line)
+            }
+        }
+    }
+}
+
+/// Parse the contents of a time.txt.
+pub fn parse(s: &str) -> Result<Time, TimeError> {
+    let mut user = None;
+    let mut system = None;
+    let mut elapsed = None;
+
+    for line in s.lines() {
+        let line = line.trim();
+        let mut words = line.words();
+
+        // bash/POSIX style puts the name first and then the value on
+        // its own; GNU style has the name as a suffix on the value,
+        // and several of them on one line.
+        let fields = match words.next() {
+            None => continue,
+            Some("user") => vec![("user", words.next())],
+            Some("sys") => vec![("system", words.next())],
+            Some("real") => vec![("elapsed", words.next())],
+            Some(_) => {
+                line.words().filter_map(|word| {
+                    ["user", "system", "elapsed"].iter()
+                        .find(|field| word.ends_with(**field))
+                        .map(|&field| (field, Some(word.slice_to(word.len() - field.len()))))
+                }).collect()
+            }
+        };
+
+        for &(field, value) in fields.iter() {
+            let d = try!(value.and_then(duration).ok_or(BadValue(field, line.into_string())));
+            match field {
+                "user" => user = Some(d),
+                "system" => system = Some(d),
+                _ => elapsed = Some(d)
+            }
+        }
+    }
+
+    match (user, system) {
+        (Some(user), Some(system)) => Ok(Time { user: user, system: system, elapsed: elapsed }),
+        (None, _) => Err(Missing("user")),
+        (_, None) => Err(Missing("system")),
+    }
+}
+
+/// Read a duration in seconds from any of `1.23`, `1,23`, `0:01.23`,
+/// `1:02:03.4`, `1m2.3s` or `2.3s`.
+fn duration(s: &str) -> Option<f64> {
+    // reading directly as f64 doesn't work on some computers (they
+    // write a comma), so normalise the separator first.
+    let s = s.replace(",", ".");
+    let s = s.as_slice();
+    if s.is_empty() { return None }
+
+    if s.contains_char(':') {
+        // [h:]m:ss.xx
+        let mut total = 0.0;
+        for part in s.split(':') {
+            let n = match from_str::<f64>(part) {
+                Some(n) if n >= 0.0 => n,
+                _ => return None
+            };
+            total = total * 60.0 + n;
+        }
+        Some(total)
+    } else if s.ends_with("s") {
+        // [Nh][Nm]N.Ns
+        let mut total = 0.0;
+        let mut rest = s.slice_to(s.len() - 1);
+        for &(unit, scale) in [('h', 3600.0), ('m', 60.0)].iter() {
+            match rest.find(unit) {
+                Some(i) => {
+                    total += match from_str::<f64>(rest.slice_to(i)) {
+                        Some(n) => n * scale,
+                        None => return None
+                    };
+                    rest = rest.slice_from(i + 1);
+                }
+                None => {}
+            }
+        }
+        from_str::<f64>(rest).map(|n| total + n)
+    } else {
+        from_str::<f64>(s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, Time, Missing, BadValue};
+
+    fn time(user: f64, system: f64, elapsed: Option<f64>) -> Time {
+        Time { user: user, system: system, elapsed: elapsed }
+    }
+
+    // the times are all exact in binary, so they can be compared
+    // exactly.
+
+    #[test]
+    fn gnu() {
+        let t = parse("83.5user 1.25system 1:25.75elapsed 98%CPU (0avgtext+0avgdata 0maxresident)k\n\
+                       0inputs+0outputs (0major+0minor)pagefaults 0swaps\n");
+        assert_eq!(t, Ok(time(83.5, 1.25, Some(85.75))));
+    }
+
+    #[test]
+    fn gnu_comma() {
+        let t = parse("83,5user 1,25system 1:25,75elapsed 98%CPU\n");
+        assert_eq!(t, Ok(time(83.5, 1.25, Some(85.75))));
+    }
+
+    #[test]
+    fn bash() {
+        let t = parse("\nreal\t1m25.75s\nuser\t1m23.5s\nsys\t0m1.25s\n");
+        assert_eq!(t, Ok(time(83.5, 1.25, Some(85.75))));
+    }
+
+    #[test]
+    fn posix() {
+        let t = parse("real 85.75\nuser 83.5\nsys 1.25\n");
+        assert_eq!(t, Ok(time(83.5, 1.25, Some(85.75))));
+    }
+
+    #[test]
+    fn hours() {
+        let t = parse("3600.5user 1.0system 1:00:02.5elapsed\n");
+        assert_eq!(t, Ok(time(3600.5, 1.0, Some(3602.5))));
+    }
+
+    #[test]
+    fn missing() {
+        assert_eq!(parse("real 85.75\nuser 83.5\n"), Err(Missing("system")));
+        assert_eq!(parse(""), Err(Missing("user")));
+    }
+
+    #[test]
+    fn bad_value() {
+        assert_eq!(parse("real 85.75\nuser lots\nsys 1.25\n"),
+                   Err(BadValue("user", "user lots".into_string())));
+    }
+}
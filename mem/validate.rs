@@ -0,0 +1,117 @@
+//! Sanity checks on a mem.json, so that bad data gets pointed out
+//! rather than plotted as if it were real.
+
+use noise;
+use Data;
+
+/// How far `max_memory` and the peak of the trace can disagree, as a
+/// fraction of the larger.
+static PEAK_TOLERANCE: f64 = 0.1;
+/// An interval between samples is a gap if it's more than this many
+/// seconds, and more than `GAP_FACTOR` times the usual interval.
+static GAP_SECONDS: f64 = 1.0;
+static GAP_FACTOR: f64 = 10.0;
+/// Nothing we benchmark uses more than this many bytes.
+static IMPLAUSIBLE_MEMORY: f64 = 64.0 * 1024.0 * 1024.0 * 1024.0;
+/// How far the last sample can be from `elapsed`, in seconds and as
+/// a fraction of `elapsed` (whichever is larger).
+static ELAPSED_SECONDS: f64 = 1.0;
+static ELAPSED_FRACTION: f64 = 0.05;
+
+/// The results of checking one commit's data.
+#[deriving(Encodable, Decodable, Clone, Show)]
+pub struct Quality {
+    pub max_memory: f64,
+    pub trace_peak: f64,
+    /// samples with an earlier time than the one before
+    pub non_monotonic: uint,
+    pub median_interval: f64,
+    pub max_gap: f64,
+    pub gaps: uint,
+    pub zero_samples: uint,
+    /// negative, NaN or absurdly large samples
+    pub implausible_samples: uint,
+    pub elapsed: f64,
+    pub last_sample: f64,
+    /// A description of each check that failed.
+    pub problems: Vec<String>,
+}
+
+/// A commit whose data failed some checks, for the run report.
+#[deriving(Encodable, Decodable, Clone)]
+pub struct Failure {
+    pub hash: String,
+    pub problems: Vec<String>,
+}
+
+/// Check `d` for internal consistency.
+pub fn check(d: &Data) -> Quality {
+    let xs = d.memory_data.as_slice();
+    let mut problems = vec![];
+    if xs.is_empty() {
+        problems.push("no memory samples".into_string());
+    }
+
+    let max_memory = d.max_memory as f64;
+    let trace_peak = xs.iter().fold(0.0, |a, &(_, m)| a.max(m));
+    let larger = max_memory.max(trace_peak);
+    if larger > 0.0 && (max_memory - trace_peak).abs() > PEAK_TOLERANCE * larger {
+        problems.push(format!("max_memory ({}) disagrees with the trace's peak ({})",
+                              max_memory, trace_peak));
+    }
+
+    let intervals: Vec<f64> = xs.windows(2).map(|w| {
+        let ((t0, _), (t1, _)) = (w[0], w[1]);
+        t1 - t0
+    }).collect();
+    let non_monotonic = intervals.iter().filter(|&&dt| dt < 0.0).count();
+    if non_monotonic > 0 {
+        problems.push(format!("{} samples go back in time", non_monotonic));
+    }
+
+    let median_interval = if intervals.is_empty() {
+        0.0
+    } else {
+        noise::median(intervals.as_slice())
+    };
+    let gap = GAP_SECONDS.max(GAP_FACTOR * median_interval);
+    let max_gap = intervals.iter().fold(0.0, |a, &dt| a.max(dt));
+    let gaps = intervals.iter().filter(|&&dt| dt > gap).count();
+    if gaps > 0 {
+        problems.push(format!("{} gaps in sampling, the longest {:.2f}s", gaps, max_gap));
+    }
+
+    let zero_samples = xs.iter().filter(|&&(_, m)| m == 0.0).count();
+    let implausible_samples = xs.iter()
+        .filter(|&&(_, m)| m.is_nan() || m < 0.0 || m > IMPLAUSIBLE_MEMORY)
+        .count();
+    if max_memory == 0.0 {
+        problems.push("max_memory is zero".into_string());
+    }
+    if zero_samples > 0 {
+        problems.push(format!("{} samples of zero memory", zero_samples));
+    }
+    if implausible_samples > 0 {
+        problems.push(format!("{} implausible memory samples", implausible_samples));
+    }
+
+    let last_sample = xs.last().map(|&(t, _)| t).unwrap_or(0.0);
+    if (d.elapsed - last_sample).abs() > ELAPSED_SECONDS.max(ELAPSED_FRACTION * d.elapsed) {
+        problems.push(format!("elapsed is {:.2f}s, but the last sample is at {:.2f}s",
+                              d.elapsed, last_sample));
+    }
+
+    Quality {
+        max_memory: max_memory,
+        trace_peak: trace_peak,
+        non_monotonic: non_monotonic,
+        median_interval: median_interval,
+        max_gap: max_gap,
+        gaps: gaps,
+        zero_samples: zero_samples,
+        implausible_samples: implausible_samples,
+        elapsed: d.elapsed,
+        last_sample: last_sample,
+        problems: problems,
+    }
+}
@@ -24,11 +24,31 @@ def tr(texts, k=None, i=None):
     return elem('tr', ''.join(texts), {'class': k, 'id': i})
 def td(text, k=None, i=None):
     return elem('td', text, {'class': k, 'id': i})
-def draw_row(pr, time, changeset, title):
+def perf(impact):
+    if impact is None:
+        return ''
+    out = []
+    for d in impact['summary']:
+        if d['name'] not in ('max_memory', 'cpu_time'):
+            continue
+        label = 'mem' if d['name'] == 'max_memory' else 'cpu'
+        if d['relative'] is not None:
+            text = '%s %+.1f%%' % (label, 100 * d['relative'])
+        else:
+            # it was 0 before
+            text = '%s new' % label
+        out.append(elem('span', text, {'class': 'perf-significant' if d['significant'] else None}))
+    if impact.get('rollup') is not None:
+        # the numbers are the whole rollup's
+        out.append('(rollup #%d)' % impact['rollup'])
+    return ' '.join(out)
+
+def draw_row(pr, time, changeset, title, impact):
     cols = [td(a(str(pr), PR_URL % pr), 'pr-number'),
             td(title, 'pr-title'),
             td(str(time), 'pr-time'),
             td(a(changeset[:8], HASH_URL % changeset), 'pr-hash'),
+            td(a(perf(impact), '../mem/pr/%d.html' % pr) if impact else '', 'pr-perf'),
             td(a('mem', '../mem/#%s' % changeset) + ' ' +
                a('buildbot', '../buildbot/#%s' % changeset), 'pr-graphs')]
     return tr(cols, 'pr', 'pr-%d' % pr)
@@ -49,8 +69,12 @@ for time, changeset, pr in cur:
         title = cgi.escape(title) # no injection here.
     except Exception as e:
         title = '<small>Unknown</small>'
+    try:
+        impact = json.load(open('%s/perf_impact.json' % dir))
+    except Exception as e:
+        impact = None
 
-    formatted.append(draw_row(pr, time, changeset, title))
+    formatted.append(draw_row(pr, time, changeset, title, impact))
 
 template = open('index.html.template').read()
 with open('index.html', 'w') as f:
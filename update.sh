@@ -22,13 +22,6 @@ git pull --rebase
             git commit -m 'Update buildbot benches.'
         )
     )
-
-    (
-        cd pull_requests
-        ./pr_list.py &&
-        git add index.html &&
-        git commit -m 'Update pull_requests.'
-    )
 )
 
 (
@@ -36,8 +29,16 @@ git pull --rebase
     rustc -O process.rs &&
       ./dl.sh &&
       ./process &&
-      git add out/*.json &&
+      git add -A out commit pr ../pull_requests &&
       git commit -m 'Update mem.'
 )
 
+# after mem, so the perf column is up to date.
+(
+    cd pull_requests
+    ./pr_list.py &&
+    git add index.html &&
+    git commit -m 'Update pull_requests.'
+)
+
 git push